@@ -1,40 +1,211 @@
+use crate::error::{CarpError, ErrorKind, Result};
+
+/// Reads bits most significant bit first.
+///
+/// Instead of indexing into `bits` for every single bit, up to 64 bits are kept in `cache`
+/// and refilled from `bits` whenever a read needs more than what is left in there.
 pub struct BitStream {
     bits: Vec<u8>,
-    pub position: usize,
+    /// Index of the first byte in `bits` that has not been loaded into `cache`
+    byte_position: usize,
+    /// Not yet consumed bits, aligned to the most significant bit
+    cache: u64,
+    /// Number of valid bits in `cache`
+    cache_bits: u64,
     pub leb_128_bytes: u64,
 }
 
+/// After a refill the cache holds at least this many bits, unless the end of data is reached
+const MIN_BITS_AFTER_REFILL: u64 = 57;
+
 impl BitStream {
     pub fn new(bits: Vec<u8>) -> BitStream {
         BitStream {
             bits,
-            position: 0,
+            byte_position: 0,
+            cache: 0,
+            cache_bits: 0,
             leb_128_bytes: 0,
         }
     }
 
-    pub fn f(&mut self, n: u64) -> u64 {
-        let mut x = 0;
-        for _ in 0..n {
-            x = 2 * x + self.read_bit() as u64;
+    pub fn f(&mut self, n: u64, element: &'static str) -> Result<u64> {
+        if n > 32 {
+            let high = self.f(n - 32, element)?;
+            let low = self.f(32, element)?;
+
+            return Ok((high << 32) | low);
+        }
+
+        let x = self.peek(n, element)?;
+        self.consume(n);
+
+        Ok(x)
+    }
+
+    /// Returns the next `n` bits without advancing the position, `n` must not exceed 57.
+    pub fn peek(&mut self, n: u64, element: &'static str) -> Result<u64> {
+        debug_assert!(n <= MIN_BITS_AFTER_REFILL);
+
+        if n == 0 {
+            return Ok(0);
         }
 
-        x
+        if self.cache_bits < n {
+            self.refill();
+
+            if self.cache_bits < n {
+                return Err(self.error(element, ErrorKind::EndOfData));
+            }
+        }
+
+        Ok(self.cache >> (64 - n))
     }
 
-    fn read_bit(&mut self) -> u8 {
-        let bit = (self.bits.get(self.position / 8).unwrap() >> (7 - self.position % 8)) & 1;
-        self.position += 1;
+    /// Advances the position by `n` bits.
+    ///
+    /// Skips that go past the cache don't read the skipped bytes at all,
+    /// which makes dropping whole OBUs cheap.
+    pub fn skip(&mut self, n: u64, element: &'static str) -> Result<()> {
+        if n <= self.cache_bits {
+            self.consume(n);
+            return Ok(());
+        }
+
+        let target = self.get_position() as u64 + n;
+        if target > self.bits.len() as u64 * 8 {
+            return Err(self.error(element, ErrorKind::EndOfData));
+        }
+
+        self.byte_position = (target / 8) as usize;
+        self.cache = 0;
+        self.cache_bits = 0;
+
+        let remainder = target % 8;
+        if remainder != 0 {
+            self.refill();
+            self.consume(remainder);
+        }
+
+        Ok(())
+    }
+
+    /// Loads as many whole bytes into the cache as fit.
+    pub fn refill(&mut self) {
+        if self.cache_bits > 56 {
+            return;
+        }
+
+        let remaining = &self.bits[self.byte_position..];
+
+        if remaining.len() >= 8 {
+            let word = u64::from_be_bytes(remaining[..8].try_into().unwrap());
+            let bytes = (64 - self.cache_bits) / 8;
+
+            self.cache |= word >> self.cache_bits;
+            self.cache_bits += bytes * 8;
+            self.byte_position += bytes as usize;
+
+            // Clear the bits of the partially loaded byte, they are loaded again on the next refill
+            self.cache &= !(u64::MAX.checked_shr(self.cache_bits as u32).unwrap_or(0));
+        } else {
+            while self.cache_bits <= 56 && self.byte_position < self.bits.len() {
+                self.cache |= (self.bits[self.byte_position] as u64) << (56 - self.cache_bits);
+                self.cache_bits += 8;
+                self.byte_position += 1;
+            }
+        }
+    }
+
+    fn consume(&mut self, n: u64) {
+        self.cache = self.cache.checked_shl(n as u32).unwrap_or(0);
+        self.cache_bits -= n;
+    }
+
+    pub fn su(&mut self, n: u64, element: &'static str) -> Result<i64> {
+        let mut value = self.f(n, element)? as i64;
+        let sign_mask = 1 << (n - 1);
+
+        if value & sign_mask != 0 {
+            value -= 2 * sign_mask;
+        }
 
-        bit
+        Ok(value)
     }
 
-    pub fn leb128(&mut self) -> u64 {
+    pub fn ns(&mut self, n: u64, element: &'static str) -> Result<u64> {
+        let w = n.ilog2() as u64 + 1;
+        let m = (1 << w) - n;
+        let v = self.f(w - 1, element)?;
+
+        if v < m {
+            return Ok(v);
+        }
+
+        let extra_bit = self.f(1, element)?;
+        Ok((v << 1) - m + extra_bit)
+    }
+
+    pub fn le(&mut self, n: u64, element: &'static str) -> Result<u64> {
+        let mut t = 0;
+        for i in 0..n {
+            let byte = self.f(8, element)?;
+            t += byte << (i * 8);
+        }
+
+        Ok(t)
+    }
+
+    pub fn byte_alignment(&mut self) -> Result<()> {
+        while self.get_position() & 7 != 0 {
+            let zero_bit = self.f(1, "zero_bit")?;
+            if zero_bit != 0 {
+                return Err(self.invalid_value("zero_bit", zero_bit));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn trailing_bits(&mut self, nb_bits: u64) -> Result<()> {
+        let trailing_one_bit = self.f(1, "trailing_one_bit")?;
+        if trailing_one_bit != 1 {
+            return Err(self.invalid_value("trailing_one_bit", trailing_one_bit));
+        }
+
+        for _ in 1..nb_bits {
+            let trailing_zero_bit = self.f(1, "trailing_zero_bit")?;
+            if trailing_zero_bit != 0 {
+                return Err(self.invalid_value("trailing_zero_bit", trailing_zero_bit));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the bits from position `start` up to `end`, the last byte is padded with zeros
+    pub fn copy_bits(&self, start: usize, end: usize) -> Vec<u8> {
+        let mut copy = vec![0; (end - start).div_ceil(8)];
+
+        for (i, position) in (start..end).enumerate() {
+            let bit = (self.bits[position / 8] >> (7 - position % 8)) & 1;
+            copy[i / 8] |= bit << (7 - i % 8);
+        }
+
+        copy
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.byte_position * 8 - self.cache_bits as usize
+    }
+
+    pub fn leb128(&mut self, element: &'static str) -> Result<u64> {
+        let start_position = self.get_position();
         let mut value = 0;
         self.leb_128_bytes = 0;
 
         for i in 0..8 {
-            let leb_128_byte = self.f(8);
+            let leb_128_byte = self.f(8, element)?;
 
             value |= (leb_128_byte & 0x7f) << (i * 7);
             self.leb_128_bytes += 1;
@@ -44,14 +215,22 @@ impl BitStream {
             }
         }
 
-        value
+        if value > u32::MAX as u64 {
+            return Err(CarpError::new(
+                start_position,
+                element,
+                ErrorKind::InvalidValue(value),
+            ));
+        }
+
+        Ok(value)
     }
 
-    pub fn uvlc(&mut self) -> u64 {
+    pub fn uvlc(&mut self, element: &'static str) -> Result<u64> {
         let mut leading_zeros = 0;
 
         loop {
-            let done = self.f(1) != 0;
+            let done = self.f(1, element)? != 0;
             if done {
                 break;
             }
@@ -60,57 +239,255 @@ impl BitStream {
         }
 
         if leading_zeros >= 32 {
-            return (1 << 32) - 1;
+            return Ok((1 << 32) - 1);
         }
 
-        let value = self.f(leading_zeros);
-        value + (1 << leading_zeros) - 1
+        let value = self.f(leading_zeros, element)?;
+        Ok(value + (1 << leading_zeros) - 1)
     }
 
     pub fn more_data_in_bitstream(&self) -> bool {
-        self.bits.len() * 8 <= self.position
+        self.get_position() < self.bits.len() * 8
+    }
+
+    /// Number of whole bytes left after the current position
+    pub fn bytes_remaining(&self) -> usize {
+        self.bits.len() - self.get_position().div_ceil(8)
+    }
+
+    pub fn error(&self, element: &'static str, kind: ErrorKind) -> CarpError {
+        CarpError::new(self.get_position(), element, kind)
+    }
+
+    pub fn invalid_value(&self, element: &'static str, value: u64) -> CarpError {
+        self.error(element, ErrorKind::InvalidValue(value))
+    }
+
+    pub fn unsupported(&self, element: &'static str) -> CarpError {
+        self.error(element, ErrorKind::Unsupported)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::BitStream;
+    use crate::error::{CarpError, ErrorKind};
 
     #[test]
     fn read_bit() {
         let mut bs = BitStream::new(vec![3, 5]);
 
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(1, bs.read_bit());
-        assert_eq!(1, bs.read_bit());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(1, bs.f(1, "bit").unwrap());
+        assert_eq!(1, bs.f(1, "bit").unwrap());
 
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(1, bs.read_bit());
-        assert_eq!(0, bs.read_bit());
-        assert_eq!(1, bs.read_bit());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(1, bs.f(1, "bit").unwrap());
+        assert_eq!(0, bs.f(1, "bit").unwrap());
+        assert_eq!(1, bs.f(1, "bit").unwrap());
     }
 
     #[test]
     fn f() {
         let mut bs = BitStream::new(vec![5, 6]);
 
-        assert_eq!(5, bs.f(8));
-        assert_eq!(6, bs.f(8));
+        assert_eq!(5, bs.f(8, "a").unwrap());
+        assert_eq!(6, bs.f(8, "b").unwrap());
     }
 
     #[test]
     fn f_more_than_one_byte() {
         let mut bs = BitStream::new(vec![5, 6]);
 
-        assert_eq!(1286, bs.f(16));
+        assert_eq!(1286, bs.f(16, "a").unwrap());
+    }
+
+    #[test]
+    fn f_end_of_data() {
+        let mut bs = BitStream::new(vec![5]);
+
+        assert_eq!(0, bs.f(4, "a").unwrap());
+        assert_eq!(
+            Err(CarpError::new(4, "b", ErrorKind::EndOfData)),
+            bs.f(8, "b")
+        );
+    }
+
+    #[test]
+    fn f_wide() {
+        let mut bs = BitStream::new(vec![0xff, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+        assert_eq!(0xf, bs.f(4, "a").unwrap());
+        assert_eq!(0xf01_0203_0405_0607, bs.f(60, "b").unwrap());
+        assert_eq!(0x0809, bs.f(16, "c").unwrap());
+        assert_eq!(10, bs.f(8, "d").unwrap());
+        assert!(!bs.more_data_in_bitstream());
+    }
+
+    #[test]
+    fn peek() {
+        let mut bs = BitStream::new(vec![0b1010_0000]);
+
+        assert_eq!(0b101, bs.peek(3, "a").unwrap());
+        assert_eq!(0b101, bs.peek(3, "a").unwrap());
+        assert_eq!(0, bs.get_position());
+        assert_eq!(
+            Err(CarpError::new(0, "b", ErrorKind::EndOfData)),
+            bs.peek(9, "b")
+        );
+    }
+
+    #[test]
+    fn skip() {
+        let mut bs = BitStream::new((0..32).collect());
+
+        bs.skip(4, "a").unwrap();
+        assert_eq!(4, bs.get_position());
+
+        // past the cache
+        bs.skip(100, "b").unwrap();
+        assert_eq!(104, bs.get_position());
+        assert_eq!(13, bs.f(8, "c").unwrap());
+
+        bs.skip(8 * 17, "d").unwrap();
+        assert_eq!(31, bs.f(8, "e").unwrap());
+
+        assert_eq!(
+            Err(CarpError::new(256, "f", ErrorKind::EndOfData)),
+            bs.skip(1, "f")
+        );
+    }
+
+    #[test]
+    fn refill_across_words() {
+        let bits: Vec<u8> = (0..20).collect();
+        let mut bs = BitStream::new(bits.clone());
+
+        bs.skip(3, "a").unwrap();
+        let mut value: u64 = 0;
+        for _ in 0..(20 * 8 - 3) {
+            value = (value << 1) | bs.f(1, "b").unwrap();
+        }
+
+        let expected = bits[12..]
+            .iter()
+            .fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+        assert_eq!(expected, value);
+        assert!(!bs.more_data_in_bitstream());
+    }
+
+    #[test]
+    fn su() {
+        let mut bs = BitStream::new(vec![0b1111_0111, 0b1000_1100]);
+
+        assert_eq!(-1, bs.su(4, "a").unwrap());
+        assert_eq!(7, bs.su(4, "b").unwrap());
+        assert_eq!(-58, bs.su(7, "c").unwrap());
+        assert_eq!(0, bs.su(1, "d").unwrap());
+    }
+
+    #[test]
+    fn ns() {
+        // n = 5: values below 3 take two bits, the others take three
+        let mut bs = BitStream::new(vec![0b1011_0111]);
+
+        assert_eq!(2, bs.ns(5, "a").unwrap());
+        assert_eq!(3, bs.ns(5, "b").unwrap());
+        assert_eq!(4, bs.ns(5, "c").unwrap());
+        assert_eq!(0, bs.ns(1, "d").unwrap());
+        assert_eq!(8, bs.get_position());
+    }
+
+    #[test]
+    fn le() {
+        let mut bs = BitStream::new(vec![0x78, 0x56, 0x34, 0x12]);
+
+        assert_eq!(0x12345678, bs.le(4, "a").unwrap());
+    }
+
+    #[test]
+    fn copy_bits() {
+        let bs = BitStream::new(vec![0b1010_1100, 0b0111_0001]);
+
+        assert_eq!(vec![0b0110_0011, 0b1000_0000], bs.copy_bits(3, 12));
+        assert_eq!(Vec::<u8>::new(), bs.copy_bits(5, 5));
+    }
+
+    #[test]
+    fn byte_alignment() {
+        let mut bs = BitStream::new(vec![0b1000_0000, 0b1010_0000, 0xff]);
+
+        assert_eq!(1, bs.f(1, "a").unwrap());
+        bs.byte_alignment().unwrap();
+        assert_eq!(8, bs.get_position());
+
+        bs.byte_alignment().unwrap();
+        assert_eq!(8, bs.get_position());
+
+        assert_eq!(1, bs.f(1, "b").unwrap());
+        assert_eq!(
+            Err(CarpError::new(11, "zero_bit", ErrorKind::InvalidValue(1))),
+            bs.byte_alignment()
+        );
+    }
+
+    #[test]
+    fn trailing_bits() {
+        let mut bs = BitStream::new(vec![0b0100_0000, 0b0001_0000]);
+
+        assert_eq!(0, bs.f(1, "a").unwrap());
+        bs.trailing_bits(7).unwrap();
+        assert_eq!(8, bs.get_position());
+
+        assert_eq!(
+            Err(CarpError::new(
+                9,
+                "trailing_one_bit",
+                ErrorKind::InvalidValue(0)
+            )),
+            bs.trailing_bits(8)
+        );
+    }
+
+    #[test]
+    fn leb128() {
+        let mut bs = BitStream::new(vec![0xe5, 0x8e, 0x26]);
+
+        assert_eq!(624485, bs.leb128("a").unwrap());
+        assert_eq!(3, bs.leb_128_bytes);
+    }
+
+    #[test]
+    fn leb128_too_large() {
+        let mut bs = BitStream::new(vec![0xff, 0xff, 0xff, 0xff, 0x7f]);
+
+        assert_eq!(
+            Err(CarpError::new(
+                0,
+                "a",
+                ErrorKind::InvalidValue(0x7_ffff_ffff)
+            )),
+            bs.leb128("a")
+        );
+    }
+
+    #[test]
+    fn uvlc() {
+        let mut bs = BitStream::new(vec![0b0010_1000]);
+
+        assert_eq!(4, bs.uvlc("a").unwrap());
+        assert_eq!(
+            Err(CarpError::new(8, "b", ErrorKind::EndOfData)),
+            bs.uvlc("b")
+        );
     }
 }
@@ -1 +1,2 @@
 pub mod bitstream;
+pub mod stream_reader;
@@ -0,0 +1,346 @@
+use std::io::{self, Read};
+
+use crate::error::{CarpError, ErrorKind, Result};
+
+use super::bitstream::BitStream;
+
+const READ_SIZE: usize = 64 * 1024;
+
+pub const OBU_TEMPORAL_DELIMITER: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamFormat {
+    /// Length delimited bitstream format from Annex B
+    AnnexB,
+    /// Low overhead bitstream format from section 5, every OBU has a size field
+    /// and temporal units start with a temporal delimiter
+    LowOverhead,
+}
+
+/// Splits a bitstream read from any `Read` source into temporal units.
+///
+/// Only one temporal unit has to be kept in memory at a time. If the source runs
+/// dry in the middle of a temporal unit, `NeedMoreData` is returned and the partial
+/// unit stays buffered, so the call can be repeated once more data is available.
+pub struct StreamReader<R: Read> {
+    reader: R,
+    format: StreamFormat,
+    /// Bytes that were read from `reader` but not returned yet
+    buffer: Vec<u8>,
+    /// Number of bytes that were returned so far
+    position: usize,
+}
+
+impl<R: Read> StreamReader<R> {
+    pub fn new(reader: R, format: StreamFormat) -> StreamReader<R> {
+        StreamReader {
+            reader,
+            format,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Creates a reader for whichever format the stream starts with.
+    ///
+    /// A low overhead stream starts with a temporal delimiter that has a size field of zero,
+    /// everything else is treated as Annex B.
+    pub fn detect(reader: R) -> Result<StreamReader<R>> {
+        let mut stream_reader = StreamReader::new(reader, StreamFormat::AnnexB);
+
+        if stream_reader.fill(2)? {
+            let obu_header = stream_reader.buffer[0];
+            let obu_type = (obu_header >> 3) & 0xf;
+            let obu_extension_flag = obu_header & 0x4 != 0;
+            let obu_has_size_field = obu_header & 0x2 != 0;
+            let size_offset = 1 + obu_extension_flag as usize;
+
+            if obu_header & 0x81 == 0
+                && obu_type == OBU_TEMPORAL_DELIMITER
+                && obu_has_size_field
+                && stream_reader.fill(size_offset + 1)?
+                && stream_reader.buffer[size_offset] == 0
+            {
+                stream_reader.format = StreamFormat::LowOverhead;
+            }
+        }
+
+        Ok(stream_reader)
+    }
+
+    pub fn format(&self) -> StreamFormat {
+        self.format
+    }
+
+    /// Returns the next temporal unit, or `None` at the end of the stream.
+    ///
+    /// For Annex B the `temporal_unit_size` is stripped, low overhead temporal units
+    /// are returned as is, starting with their temporal delimiter.
+    pub fn read_temporal_unit(&mut self) -> Result<Option<Vec<u8>>> {
+        if !self.fill(1)? {
+            return Ok(None);
+        }
+
+        match self.format {
+            StreamFormat::AnnexB => self.read_annex_b_temporal_unit(),
+            StreamFormat::LowOverhead => self.read_low_overhead_temporal_unit(),
+        }
+    }
+
+    fn read_annex_b_temporal_unit(&mut self) -> Result<Option<Vec<u8>>> {
+        let (temporal_unit_size, size_bytes) = self.leb128(0, "temporal_unit_size")?;
+
+        let unit_bytes = size_bytes + temporal_unit_size as usize;
+        if !self.fill(unit_bytes)? {
+            return Err(self.error("temporal_unit", ErrorKind::NeedMoreData));
+        }
+
+        let temporal_unit = self.buffer[size_bytes..unit_bytes].to_vec();
+        self.buffer.drain(..unit_bytes);
+        self.position += unit_bytes;
+
+        Ok(Some(temporal_unit))
+    }
+
+    /// Collects OBUs until the next temporal delimiter.
+    ///
+    /// The end of a temporal unit is only known once the next one starts, so the last
+    /// temporal unit of a source that is still being written reports `NeedMoreData`.
+    fn read_low_overhead_temporal_unit(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut unit_bytes = 0;
+
+        loop {
+            unit_bytes += self.obu_bytes(unit_bytes)?;
+
+            if !self.fill(unit_bytes + 1)? {
+                break;
+            }
+
+            if (self.buffer[unit_bytes] >> 3) & 0xf == OBU_TEMPORAL_DELIMITER {
+                break;
+            }
+        }
+
+        let temporal_unit = self.buffer.drain(..unit_bytes).collect();
+        self.position += unit_bytes;
+
+        Ok(Some(temporal_unit))
+    }
+
+    /// Returns the size of the OBU starting at `offset` in the buffer, including its header.
+    fn obu_bytes(&mut self, offset: usize) -> Result<usize> {
+        let obu_header = self.buffer[offset];
+        let obu_extension_flag = obu_header & 0x4 != 0;
+        let obu_has_size_field = obu_header & 0x2 != 0;
+
+        if !obu_has_size_field {
+            return Err(CarpError::new(
+                (self.position + offset) * 8 + 6,
+                "obu_has_size_field",
+                ErrorKind::InvalidValue(0),
+            ));
+        }
+
+        let header_bytes = 1 + obu_extension_flag as usize;
+        let (obu_size, size_bytes) = self.leb128(offset + header_bytes, "obu_size")?;
+
+        let obu_bytes = header_bytes + size_bytes + obu_size as usize;
+        if !self.fill(offset + obu_bytes)? {
+            return Err(self.error("open_bitstream_unit", ErrorKind::NeedMoreData));
+        }
+
+        Ok(obu_bytes)
+    }
+
+    /// Decodes a leb128 value at `offset` in the buffer without consuming it.
+    fn leb128(&mut self, offset: usize, element: &'static str) -> Result<(u64, usize)> {
+        let mut leb_128_bytes = 0;
+        while leb_128_bytes < 8 {
+            if !self.fill(offset + leb_128_bytes + 1)? {
+                return Err(self.error(element, ErrorKind::NeedMoreData));
+            }
+
+            leb_128_bytes += 1;
+            if self.buffer[offset + leb_128_bytes - 1] & 0x80 == 0 {
+                break;
+            }
+        }
+
+        let mut b = BitStream::new(self.buffer[offset..offset + leb_128_bytes].to_vec());
+        let value = b.leb128(element).map_err(|err| {
+            CarpError::new(
+                (self.position + offset) * 8 + err.position,
+                err.element,
+                err.kind,
+            )
+        })?;
+
+        Ok((value, leb_128_bytes))
+    }
+
+    /// Reads until at least `n` bytes are buffered, returns false if the source ended before that.
+    ///
+    /// The buffer grows by at most `READ_SIZE` per read, so a bogus size does not allocate
+    /// more than the source actually holds.
+    fn fill(&mut self, n: usize) -> Result<bool> {
+        while self.buffer.len() < n {
+            let start = self.buffer.len();
+            self.buffer.resize(start + READ_SIZE, 0);
+
+            let read = self.reader.read(&mut self.buffer[start..]);
+            self.buffer.truncate(start + *read.as_ref().unwrap_or(&0));
+
+            match read {
+                Ok(0) => return Ok(false),
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    return Err(self.error("temporal_unit", ErrorKind::NeedMoreData));
+                }
+                Err(err) => return Err(self.error("temporal_unit", ErrorKind::Io(err.kind()))),
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn error(&self, element: &'static str, kind: ErrorKind) -> CarpError {
+        CarpError::new(self.position * 8, element, kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, io};
+
+    use super::{StreamFormat, StreamReader};
+    use crate::error::{CarpError, ErrorKind};
+
+    /// Hands out one chunk per read and reports `WouldBlock` when it runs out
+    struct Chunks(VecDeque<Vec<u8>>);
+
+    impl io::Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.pop_front() {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                None => Err(io::ErrorKind::WouldBlock.into()),
+            }
+        }
+    }
+
+    #[test]
+    fn read_temporal_units() {
+        let data: &[u8] = &[2, 5, 6, 0, 1, 7];
+        let mut reader = StreamReader::new(data, StreamFormat::AnnexB);
+
+        assert_eq!(Some(vec![5, 6]), reader.read_temporal_unit().unwrap());
+        assert_eq!(Some(vec![]), reader.read_temporal_unit().unwrap());
+        assert_eq!(Some(vec![7]), reader.read_temporal_unit().unwrap());
+        assert_eq!(None, reader.read_temporal_unit().unwrap());
+    }
+
+    #[test]
+    fn truncated_temporal_unit() {
+        let data: &[u8] = &[1, 5, 3, 1];
+        let mut reader = StreamReader::new(data, StreamFormat::AnnexB);
+
+        assert_eq!(Some(vec![5]), reader.read_temporal_unit().unwrap());
+        assert_eq!(
+            Err(CarpError::new(16, "temporal_unit", ErrorKind::NeedMoreData)),
+            reader.read_temporal_unit()
+        );
+    }
+
+    #[test]
+    fn huge_temporal_unit_size() {
+        // temporal_unit_size of u32::MAX followed by a single byte
+        let data: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x0f, 1];
+        let mut reader = StreamReader::new(data, StreamFormat::AnnexB);
+
+        assert_eq!(
+            Err(CarpError::new(0, "temporal_unit", ErrorKind::NeedMoreData)),
+            reader.read_temporal_unit()
+        );
+        assert!(reader.buffer.capacity() < 1 << 20);
+    }
+
+    #[test]
+    fn need_more_data() {
+        let chunks = Chunks(VecDeque::from([vec![0x83], vec![0x00, 1, 2]]));
+        let mut reader = StreamReader::new(chunks, StreamFormat::AnnexB);
+
+        assert_eq!(
+            ErrorKind::NeedMoreData,
+            reader.read_temporal_unit().unwrap_err().kind
+        );
+        assert_eq!(
+            ErrorKind::NeedMoreData,
+            reader.read_temporal_unit().unwrap_err().kind
+        );
+
+        reader.reader.0.push_back(vec![3]);
+        assert_eq!(Some(vec![1, 2, 3]), reader.read_temporal_unit().unwrap());
+    }
+
+    const TEMPORAL_DELIMITER: [u8; 2] = [0x12, 0x00];
+    const PADDING: [u8; 3] = [0x7a, 0x01, 0xaa];
+
+    #[test]
+    fn read_low_overhead_temporal_units() {
+        let data = [
+            &TEMPORAL_DELIMITER[..],
+            &PADDING,
+            &TEMPORAL_DELIMITER,
+            &PADDING,
+            &PADDING,
+        ]
+        .concat();
+        let mut reader = StreamReader::new(&data[..], StreamFormat::LowOverhead);
+
+        assert_eq!(
+            Some([&TEMPORAL_DELIMITER[..], &PADDING].concat()),
+            reader.read_temporal_unit().unwrap()
+        );
+        assert_eq!(
+            Some([&TEMPORAL_DELIMITER[..], &PADDING, &PADDING].concat()),
+            reader.read_temporal_unit().unwrap()
+        );
+        assert_eq!(None, reader.read_temporal_unit().unwrap());
+    }
+
+    #[test]
+    fn low_overhead_without_size_field() {
+        let data: &[u8] = &[0x12, 0x00, 0x78, 0xaa];
+        let mut reader = StreamReader::new(data, StreamFormat::LowOverhead);
+
+        assert_eq!(
+            Err(CarpError::new(
+                22,
+                "obu_has_size_field",
+                ErrorKind::InvalidValue(0)
+            )),
+            reader.read_temporal_unit()
+        );
+    }
+
+    #[test]
+    fn detect() {
+        let low_overhead = [&TEMPORAL_DELIMITER[..], &PADDING].concat();
+        let reader = StreamReader::detect(&low_overhead[..]).unwrap();
+        assert_eq!(StreamFormat::LowOverhead, reader.format());
+
+        // temporal_unit_size, frame_unit_size, obu_length and a temporal delimiter without size
+        let annex_b: &[u8] = &[3, 2, 1, 0x10];
+        let reader = StreamReader::detect(annex_b).unwrap();
+        assert_eq!(StreamFormat::AnnexB, reader.format());
+
+        let mut reader = StreamReader::detect(&low_overhead[..]).unwrap();
+        assert_eq!(
+            Some(low_overhead.clone()),
+            reader.read_temporal_unit().unwrap()
+        );
+    }
+}
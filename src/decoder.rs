@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+
+use crate::{
+    bits::stream_reader::StreamFormat,
+    error::Result,
+    obu::{
+        obu_frame_header::UncompressedHeader,
+        obu_sequence_header::{ObuSequenceHeader, OperatingPointSelection},
+        open_bitstream_unit::ObuPayload,
+    },
+    parser::Parser,
+    temporal_unit::TemporalUnit,
+};
+
+/// A frame that is output by the decoder, either directly or through `show_existing_frame`
+pub struct Frame {
+    /// Presentation timestamp of the temporal unit the frame was sent with
+    pub pts: Option<u64>,
+    /// Presentation time in seconds from `frame_presentation_time`, counted from the last
+    /// random access point and wrapping around with the length of `frame_presentation_time`
+    pub presentation_time: Option<f64>,
+    pub header: UncompressedHeader,
+}
+
+/// Parses temporal units and hands out the frames they contain.
+///
+/// Data is passed in one temporal unit at a time with `send_data`,
+/// afterwards `receive_frame` returns the shown frames until it returns `None`.
+pub struct Decoder {
+    format: StreamFormat,
+    parser: Parser,
+    frames: VecDeque<Frame>,
+    temporal_unit: Option<TemporalUnit>,
+}
+
+impl Decoder {
+    pub fn new(format: StreamFormat) -> Decoder {
+        Decoder {
+            format,
+            parser: Parser::default(),
+            frames: VecDeque::new(),
+            temporal_unit: None,
+        }
+    }
+
+    /// Parses a single temporal unit.
+    ///
+    /// For Annex B the data must not include the `temporal_unit_size`.
+    /// Decoding can continue with the next temporal unit after an error.
+    pub fn send_data(&mut self, data: Vec<u8>, pts: Option<u64>) -> Result<()> {
+        self.temporal_unit = None;
+        let temporal_unit = self.parser.decode_temporal_unit(data, self.format, pts)?;
+
+        for obu in &temporal_unit.obus {
+            // A redundant frame header is only a new frame if the original one was lost
+            let frame_header = match &obu.payload {
+                ObuPayload::FrameHeader(frame_header)
+                | ObuPayload::RedundantFrameHeader(frame_header)
+                | ObuPayload::Frame { frame_header, .. } => frame_header,
+                _ => continue,
+            };
+            if frame_header.frame_header_copy {
+                continue;
+            }
+
+            let header = frame_header.uncompressed_header.clone();
+            if header.show_frame || header.show_existing_frame {
+                let timing_info = self
+                    .sequence_header()
+                    .and_then(|sh| sh.timing_info.as_ref());
+                let presentation_time = timing_info
+                    .zip(header.frame_presentation_time)
+                    .map(|(timing_info, time)| timing_info.presentation_time(time));
+
+                self.frames.push_back(Frame {
+                    pts,
+                    presentation_time,
+                    header,
+                });
+            }
+        }
+
+        self.temporal_unit = Some(temporal_unit);
+
+        Ok(())
+    }
+
+    /// The OBUs of the temporal unit that was parsed last, `None` if it had an error
+    pub fn temporal_unit(&self) -> Option<&TemporalUnit> {
+        self.temporal_unit.as_ref()
+    }
+
+    /// Sets how the operating point is chosen, takes effect with the next sequence header
+    pub fn set_operating_point_selection(&mut self, selection: OperatingPointSelection) {
+        self.parser.state.operating_point_selection = selection;
+    }
+
+    /// Index of the operating point that was chosen for the current sequence header
+    pub fn operating_point(&self) -> usize {
+        self.parser.state.operating_point
+    }
+
+    pub fn receive_frame(&mut self) -> Option<Frame> {
+        self.frames.pop_front()
+    }
+
+    /// The most recently received sequence header
+    pub fn sequence_header(&self) -> Option<&ObuSequenceHeader> {
+        self.parser.state.sequence_header.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decoder;
+    use crate::{bits::stream_reader::StreamFormat, error::ErrorKind};
+
+    #[test]
+    fn continue_after_size_mismatch() {
+        let mut decoder = Decoder::new(StreamFormat::AnnexB);
+
+        // The frame unit is larger than the temporal unit
+        let err = decoder.send_data(vec![3, 1, 0x10], Some(0)).unwrap_err();
+        assert_eq!("frame_unit_size", err.element);
+        assert_eq!(
+            ErrorKind::SizeMismatch {
+                expected: 2,
+                actual: 3
+            },
+            err.kind
+        );
+
+        decoder.send_data(vec![], Some(1)).unwrap();
+        assert!(decoder.receive_frame().is_none());
+        assert!(decoder.sequence_header().is_none());
+    }
+
+    #[test]
+    fn frame_size_from_empty_reference_slot() {
+        let mut decoder = Decoder::new(StreamFormat::LowOverhead);
+
+        // Temporal delimiter, 352x288 sequence header and an inter frame header that takes
+        // its size from a reference slot that was never filled
+        let data = vec![
+            0x12, 0x00, 0x0a, 10, 0x00, 0x00, 0x00, 0x04, 0x45, 0x7e, 0x3e, 0x00, 0x00, 0x04, 0x1a,
+            6, 0x33, 0xc0, 0x00, 0x00, 0x01, 0x80,
+        ];
+
+        let err = decoder.send_data(data, None).unwrap_err();
+        assert_eq!("found_ref", err.element);
+        assert_eq!(ErrorKind::FrameLoss, err.kind);
+        assert!(decoder.receive_frame().is_none());
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, CarpError>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CarpError {
+    /// Bit offset into the bitstream at which the error was detected
+    pub position: usize,
+    /// Name of the syntax element that was being parsed
+    pub element: &'static str,
+    pub kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The bitstream ended before the syntax element was complete
+    EndOfData,
+    /// The syntax element has a value that is not allowed by the specification
+    InvalidValue(u64),
+    /// The syntax element requires a feature that is not implemented yet
+    Unsupported,
+    /// A frame header was found before any sequence header
+    MissingSequenceHeader,
+    /// A tile group was found without a frame header of its frame
+    MissingFrameHeader,
+    /// A frame refers to a reference frame that was lost, decoding can resume with the next
+    /// key frame
+    FrameLoss,
+    /// A size does not fit into the size of the enclosing unit, or does not match the number of
+    /// bytes that were actually used. Both values are in bytes.
+    SizeMismatch { expected: u64, actual: u64 },
+    /// A copy of the frame header differs from the first frame header of the frame
+    FrameHeaderMismatch,
+    /// The input source has no more data available right now, but a unit was only partially read
+    NeedMoreData,
+    /// Reading from the input source failed
+    Io(std::io::ErrorKind),
+}
+
+impl CarpError {
+    pub fn new(position: usize, element: &'static str, kind: ErrorKind) -> CarpError {
+        CarpError {
+            position,
+            element,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for CarpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::EndOfData => write!(f, "unexpected end of data"),
+            ErrorKind::InvalidValue(value) => write!(f, "invalid value {value}"),
+            ErrorKind::Unsupported => write!(f, "not supported"),
+            ErrorKind::MissingSequenceHeader => write!(f, "missing sequence header"),
+            ErrorKind::MissingFrameHeader => write!(f, "missing frame header"),
+            ErrorKind::FrameLoss => write!(f, "reference frame lost"),
+            ErrorKind::SizeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "size mismatch, expected {expected} bytes but got {actual}"
+                )
+            }
+            ErrorKind::FrameHeaderMismatch => write!(f, "frame header copy differs"),
+            ErrorKind::NeedMoreData => write!(f, "need more data"),
+            ErrorKind::Io(kind) => write!(f, "i/o error ({kind})"),
+        }?;
+
+        write!(f, " in {} at bit {}", self.element, self.position)
+    }
+}
+
+impl std::error::Error for CarpError {}
@@ -0,0 +1,201 @@
+use std::io::BufRead;
+
+use carp::{
+    bits::stream_reader::StreamFormat, CarpError, ColorConfig, Decoder, ObuSequenceHeader, Result,
+    TimingInfo,
+};
+
+use crate::input::Input;
+
+/// Prints a summary of the stream and of every distinct sequence header in it.
+///
+/// Frames that can't be parsed don't stop the summary, they are counted and the
+/// first error is printed at the end.
+pub fn info<R: BufRead>(input: R) -> Result<()> {
+    let mut input = Input::new(input)?;
+
+    match &input {
+        Input::Ivf(reader) => {
+            let header = reader.header();
+            println!(
+                "format: IVF, fourcc {}, {}x{}, timebase {}/{}",
+                String::from_utf8_lossy(&header.fourcc),
+                header.width,
+                header.height,
+                header.timebase_numerator,
+                header.timebase_denominator
+            );
+        }
+        Input::Stream(reader) => match reader.format() {
+            StreamFormat::AnnexB => println!("format: Annex B"),
+            StreamFormat::LowOverhead => println!("format: low overhead (section 5)"),
+        },
+    }
+
+    let mut decoder = Decoder::new(input.format());
+    let mut sequence_header: Option<ObuSequenceHeader> = None;
+    let mut temporal_units = 0;
+    let mut frames = 0;
+    let mut missing_temporal_delimiters = 0;
+    let mut errors: Vec<CarpError> = Vec::new();
+
+    while let Some((data, pts)) = input.read_temporal_unit()? {
+        temporal_units += 1;
+
+        if let Err(err) = decoder.send_data(data, pts) {
+            errors.push(err);
+        }
+
+        if decoder
+            .temporal_unit()
+            .is_some_and(|temporal_unit| temporal_unit.missing_temporal_delimiter)
+        {
+            missing_temporal_delimiters += 1;
+        }
+
+        while decoder.receive_frame().is_some() {
+            frames += 1;
+        }
+
+        if let Some(sh) = decoder.sequence_header() {
+            if sequence_header.as_ref() != Some(sh) {
+                print_sequence_header(sh);
+                sequence_header = Some(sh.clone());
+            }
+        }
+    }
+
+    println!("temporal units: {temporal_units}");
+    println!("frames: {frames}");
+
+    if missing_temporal_delimiters > 0 {
+        println!("temporal units without temporal delimiter: {missing_temporal_delimiters}");
+    }
+
+    if let Some(err) = errors.first() {
+        println!(
+            "temporal units with errors: {}, first error: {err}",
+            errors.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn print_sequence_header(sh: &ObuSequenceHeader) {
+    println!("sequence header");
+    println!("  profile: {}", sh.seq_profile());
+    println!("  still picture: {}", sh.still_picture());
+    println!(
+        "  max frame size: {}x{}",
+        sh.max_frame_width_minus_1() + 1,
+        sh.max_frame_height_minus_1() + 1
+    );
+    print_color_config(sh.color_config());
+
+    for (i, op) in sh.operating_points().iter().enumerate() {
+        print!(
+            "  operating point {i}: idc {:#05x}, level {}, tier {}",
+            op.operating_point_idc(),
+            match op.level() {
+                Some((major, minor)) => format!("{major}.{minor}"),
+                None => "max".to_string(),
+            },
+            op.seq_tier()
+        );
+
+        if let Some(info) = op.operating_parameters_info() {
+            print!(
+                ", decoder buffer delay {}, encoder buffer delay {}",
+                info.decoder_buffer_delay(),
+                info.encoder_buffer_delay()
+            );
+            if info.low_delay_mode_flag() {
+                print!(", low delay mode");
+            }
+        }
+
+        if let Some(delay) = op.initial_display_delay() {
+            print!(", initial display delay {delay}");
+        }
+
+        println!();
+    }
+
+    match sh.timing_info() {
+        Some(timing_info) => print_timing_info(timing_info),
+        None => println!("  timing info: not present"),
+    }
+
+    println!("  coding tools: {}", coding_tools(sh).join(", "));
+}
+
+fn print_color_config(cc: &ColorConfig) {
+    println!("  bit depth: {}", cc.bit_depth());
+
+    let subsampling = match (cc.mono_chrome(), cc.subsampling_x(), cc.subsampling_y()) {
+        (true, _, _) => "4:0:0",
+        (false, true, true) => "4:2:0",
+        (false, true, false) => "4:2:2",
+        (false, false, false) => "4:4:4",
+        (false, false, true) => "4:4:0",
+    };
+    println!("  subsampling: {subsampling}");
+
+    println!(
+        "  color: primaries {}, transfer characteristics {}, matrix coefficients {}, {} range",
+        cc.color_primaries(),
+        cc.transfer_characteristics(),
+        cc.matrix_coefficients(),
+        match cc.color_range() {
+            true => "full",
+            false => "studio",
+        }
+    );
+}
+
+fn print_timing_info(timing_info: &TimingInfo) {
+    print!(
+        "  timing info: {} units per tick, time scale {}",
+        timing_info.num_units_in_display_tick(),
+        timing_info.time_scale()
+    );
+
+    if timing_info.equal_picture_interval() {
+        print!(
+            ", {} ticks per picture",
+            timing_info.num_ticks_per_picture_minus_1() + 1
+        );
+    }
+
+    println!();
+}
+
+fn coding_tools(sh: &ObuSequenceHeader) -> Vec<&'static str> {
+    let tools = [
+        (sh.use_128x128_superblock(), "128x128 superblocks"),
+        (sh.enable_filter_intra(), "filter intra"),
+        (sh.enable_intra_edge_filter(), "intra edge filter"),
+        (sh.enable_interintra_compound(), "interintra compound"),
+        (sh.enable_masked_compound(), "masked compound"),
+        (sh.enable_warped_motion(), "warped motion"),
+        (sh.enable_dual_filter(), "dual filter"),
+        (sh.enable_order_hint(), "order hint"),
+        (sh.enable_jnt_comp(), "distance weighted compound"),
+        (sh.enable_ref_frame_mvs(), "reference frame motion vectors"),
+        (
+            sh.seq_force_screen_content_tools() > 0,
+            "screen content tools",
+        ),
+        (sh.enable_superres(), "superres"),
+        (sh.enable_cdef(), "cdef"),
+        (sh.enable_restoration(), "loop restoration"),
+        (sh.film_grain_params_present(), "film grain"),
+    ];
+
+    tools
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| name)
+        .collect()
+}
@@ -0,0 +1,42 @@
+use std::io::BufRead;
+
+use carp::{
+    bits::stream_reader::{StreamFormat, StreamReader},
+    ivf::IvfReader,
+    Result,
+};
+
+/// An input file in any of the supported formats
+pub enum Input<R: BufRead> {
+    Ivf(IvfReader<R>),
+    Stream(StreamReader<R>),
+}
+
+impl<R: BufRead> Input<R> {
+    pub fn new(mut input: R) -> Result<Input<R>> {
+        let is_ivf = input.fill_buf().is_ok_and(|data| data.starts_with(b"DKIF"));
+
+        match is_ivf {
+            true => Ok(Input::Ivf(IvfReader::new(input)?)),
+            false => Ok(Input::Stream(StreamReader::detect(input)?)),
+        }
+    }
+
+    /// The format of the OBUs, IVF always contains low overhead temporal units
+    pub fn format(&self) -> StreamFormat {
+        match self {
+            Input::Ivf(_) => StreamFormat::LowOverhead,
+            Input::Stream(reader) => reader.format(),
+        }
+    }
+
+    /// Returns the next temporal unit and its pts, or `None` at the end of the input.
+    pub fn read_temporal_unit(&mut self) -> Result<Option<(Vec<u8>, Option<u64>)>> {
+        match self {
+            Input::Ivf(reader) => Ok(reader
+                .read_frame()?
+                .map(|frame| (frame.data, Some(frame.pts)))),
+            Input::Stream(reader) => Ok(reader.read_temporal_unit()?.map(|data| (data, None))),
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::Read;
+
+use crate::{
+    bits::bitstream::BitStream,
+    error::{CarpError, ErrorKind, Result},
+};
+
+const IVF_SIGNATURE: &[u8; 4] = b"DKIF";
+const IVF_FILE_HEADER_SIZE: usize = 32;
+const IVF_FRAME_HEADER_SIZE: usize = 12;
+
+pub struct IvfHeader {
+    pub version: u64,
+    pub header_size: u64,
+    pub fourcc: [u8; 4],
+    pub width: u64,
+    pub height: u64,
+    pub timebase_denominator: u64,
+    pub timebase_numerator: u64,
+    pub frame_count: u64,
+}
+
+impl IvfHeader {
+    fn new(b: &mut BitStream) -> Result<IvfHeader> {
+        let signature = b.f(32, "signature")?;
+        if signature != u32::from_be_bytes(*IVF_SIGNATURE) as u64 {
+            return Err(b.invalid_value("signature", signature));
+        }
+
+        let version = b.le(2, "version")?;
+        let header_size = b.le(2, "header_size")?;
+        if header_size < IVF_FILE_HEADER_SIZE as u64 {
+            return Err(b.invalid_value("header_size", header_size));
+        }
+
+        let fourcc = (b.f(32, "fourcc")? as u32).to_be_bytes();
+        let width = b.le(2, "width")?;
+        let height = b.le(2, "height")?;
+        let timebase_denominator = b.le(4, "timebase_denominator")?;
+        let timebase_numerator = b.le(4, "timebase_numerator")?;
+        let frame_count = b.le(4, "frame_count")?;
+
+        Ok(IvfHeader {
+            version,
+            header_size,
+            fourcc,
+            width,
+            height,
+            timebase_denominator,
+            timebase_numerator,
+            frame_count,
+        })
+    }
+}
+
+/// A frame of an IVF file, for AV1 this is a temporal unit in the low overhead format
+pub struct IvfFrame {
+    pub pts: u64,
+    pub data: Vec<u8>,
+}
+
+pub struct IvfReader<R: Read> {
+    reader: R,
+    header: IvfHeader,
+    /// Number of bytes that were read so far
+    position: usize,
+}
+
+impl<R: Read> IvfReader<R> {
+    pub fn new(mut reader: R) -> Result<IvfReader<R>> {
+        let data = read_bytes(&mut reader, 0, IVF_FILE_HEADER_SIZE, "ivf_header")?
+            .ok_or_else(|| CarpError::new(0, "ivf_header", ErrorKind::EndOfData))?;
+        let header = IvfHeader::new(&mut BitStream::new(data))?;
+
+        let extra_header_bytes = header.header_size as usize - IVF_FILE_HEADER_SIZE;
+        read_bytes(
+            &mut reader,
+            IVF_FILE_HEADER_SIZE,
+            extra_header_bytes,
+            "ivf_header",
+        )?;
+
+        Ok(IvfReader {
+            reader,
+            position: header.header_size as usize,
+            header,
+        })
+    }
+
+    pub fn header(&self) -> &IvfHeader {
+        &self.header
+    }
+
+    /// Returns the next frame, or `None` at the end of the file.
+    pub fn read_frame(&mut self) -> Result<Option<IvfFrame>> {
+        let Some(frame_header) = self.read_bytes(IVF_FRAME_HEADER_SIZE, "frame_header")? else {
+            return Ok(None);
+        };
+
+        let mut b = BitStream::new(frame_header);
+        let frame_size = b.le(4, "frame_size")? as usize;
+        let pts = b.le(8, "pts")?;
+
+        let data = match frame_size {
+            0 => Vec::new(),
+            _ => self
+                .read_bytes(frame_size, "frame")?
+                .ok_or_else(|| CarpError::new(self.position * 8, "frame", ErrorKind::EndOfData))?,
+        };
+
+        Ok(Some(IvfFrame { pts, data }))
+    }
+
+    fn read_bytes(&mut self, n: usize, element: &'static str) -> Result<Option<Vec<u8>>> {
+        let data = read_bytes(&mut self.reader, self.position, n, element)?;
+        if data.is_some() {
+            self.position += n;
+        }
+
+        Ok(data)
+    }
+}
+
+/// Reads exactly `n` bytes, returns `None` if the reader ended before the first byte.
+fn read_bytes<R: Read>(
+    reader: &mut R,
+    position: usize,
+    n: usize,
+    element: &'static str,
+) -> Result<Option<Vec<u8>>> {
+    // `n` comes from the file, so the buffer only grows with the bytes actually read
+    let mut data = Vec::new();
+    reader
+        .take(n as u64)
+        .read_to_end(&mut data)
+        .map_err(|err| CarpError::new(position * 8, element, ErrorKind::Io(err.kind())))?;
+
+    if data.is_empty() && n > 0 {
+        return Ok(None);
+    }
+
+    if data.len() < n {
+        return Err(CarpError::new(
+            (position + data.len()) * 8,
+            element,
+            ErrorKind::EndOfData,
+        ));
+    }
+
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IvfReader;
+    use crate::error::{CarpError, ErrorKind};
+
+    fn ivf_header() -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(b"DKIF");
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&32u16.to_le_bytes());
+        header.extend_from_slice(b"AV01");
+        header.extend_from_slice(&352u16.to_le_bytes());
+        header.extend_from_slice(&288u16.to_le_bytes());
+        header.extend_from_slice(&30u32.to_le_bytes());
+        header.extend_from_slice(&1u32.to_le_bytes());
+        header.extend_from_slice(&2u32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+
+        header
+    }
+
+    fn ivf_frame(pts: u64, data: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&pts.to_le_bytes());
+        frame.extend_from_slice(data);
+
+        frame
+    }
+
+    #[test]
+    fn ivf_header_and_frames() {
+        let data = [ivf_header(), ivf_frame(0, &[1, 2, 3]), ivf_frame(1, &[4])].concat();
+        let mut reader = IvfReader::new(&data[..]).unwrap();
+
+        let header = reader.header();
+        assert_eq!(b"AV01", &header.fourcc);
+        assert_eq!(352, header.width);
+        assert_eq!(288, header.height);
+        assert_eq!(30, header.timebase_denominator);
+        assert_eq!(1, header.timebase_numerator);
+        assert_eq!(2, header.frame_count);
+
+        let frame = reader.read_frame().unwrap().unwrap();
+        assert_eq!(0, frame.pts);
+        assert_eq!(vec![1, 2, 3], frame.data);
+
+        let frame = reader.read_frame().unwrap().unwrap();
+        assert_eq!(1, frame.pts);
+        assert_eq!(vec![4], frame.data);
+
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let mut data = ivf_header();
+        data[0] = b'X';
+
+        let err = IvfReader::new(&data[..]).err().unwrap();
+        assert_eq!("signature", err.element);
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let mut data = [ivf_header(), ivf_frame(7, &[1, 2, 3])].concat();
+        data.pop();
+        let mut reader = IvfReader::new(&data[..]).unwrap();
+
+        assert_eq!(
+            Err(CarpError::new(
+                (32 + 12 + 2) * 8,
+                "frame",
+                ErrorKind::EndOfData
+            )),
+            reader
+                .read_frame()
+                .map(|frame| frame.map(|frame| frame.pts))
+        );
+    }
+
+    #[test]
+    fn huge_frame_size() {
+        let mut data = [ivf_header(), ivf_frame(0, &[1])].concat();
+        data[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut reader = IvfReader::new(&data[..]).unwrap();
+
+        assert_eq!(
+            Err(CarpError::new(
+                (32 + 12 + 1) * 8,
+                "frame",
+                ErrorKind::EndOfData
+            )),
+            reader
+                .read_frame()
+                .map(|frame| frame.map(|frame| frame.pts))
+        );
+    }
+}
@@ -0,0 +1,23 @@
+pub mod bits;
+pub mod decoder;
+pub mod error;
+pub mod ivf;
+pub mod obu;
+mod parser;
+pub mod temporal_unit;
+#[cfg(test)]
+mod test_util;
+
+pub use decoder::{Decoder, Frame};
+pub use error::{CarpError, ErrorKind, Result};
+pub use obu::{
+    color_config::ColorConfig,
+    obu_frame_header::UncompressedHeader,
+    obu_header::ObuHeader,
+    obu_sequence_header::{ObuSequenceHeader, OperatingPointSelection},
+    operating_point::OperatingPoint,
+    tile_layout::TileLayout,
+    timing_info::TimingInfo,
+};
+
+use parser::State;
@@ -1,69 +1,83 @@
-use bits::bitstream::BitStream;
-use obu::open_bitstream_unit::OpenBitstreamUnit;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+};
 
-mod bits;
-mod obu;
+use carp::{Decoder, ErrorKind, Result};
+use input::Input;
+
+mod info;
+mod input;
+
+const USAGE: &str = "usage: carp [info] [FILE]";
 
 fn main() {
-    let mut b = BitStream::new(vec![]);
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
 
-    let mut p = Parser::default();
+    let show_info = args.first().is_some_and(|arg| arg == "info");
+    if show_info {
+        args.remove(0);
+    }
 
-    p.bitstream(&mut b);
-}
+    if args.len() > 1 {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    }
 
-#[derive(Default)]
-struct Parser {
-    state: State,
-}
+    let input: Box<dyn Read> = match args.first() {
+        Some(path) => match File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(io::stdin().lock()),
+    };
 
-#[derive(Default)]
-pub struct State {
-    operating_point_idc: u64,
-    order_hint: u64,
-    order_hint_bits: u64,
-    bit_depth: u64,
-    num_planes: u64,
-    seen_frame_header: bool,
-    tile_num: bool,
-    frame_is_intra: bool,
-    ref_frame_type: Vec<u64>,
-    ref_valid: Vec<bool>,
-    ref_order_hint: Vec<u64>,
-    order_hints: Vec<u64>,
-    prev_frame_id: u64,
-    upscaled_width: u64,
-    frame_width: u64,
-    delta_frame_id: u64,
-    ref_frame_sign_bias: Vec<u64>,
+    let result = match show_info {
+        true => info::info(BufReader::new(input)),
+        false => frames(BufReader::new(input)),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 }
 
-impl Parser {
-    fn bitstream(&mut self, b: &mut BitStream) {
-        while b.more_data_in_bitstream() {
-            let temporal_unit_size = b.leb128();
+/// Prints every frame that is output by the decoder
+fn frames<R: BufRead>(input: R) -> Result<()> {
+    let mut input = Input::new(input)?;
+    let mut decoder = Decoder::new(input.format());
 
-            self.temporal_unit(b, temporal_unit_size);
+    while let Some((data, pts)) = input.read_temporal_unit()? {
+        match decoder.send_data(data, pts) {
+            // Temporal units are delimited independently, so decoding can resume at the next one
+            Err(err)
+                if matches!(
+                    err.kind,
+                    ErrorKind::SizeMismatch { .. }
+                        | ErrorKind::FrameLoss
+                        | ErrorKind::MissingFrameHeader
+                        | ErrorKind::FrameHeaderMismatch
+                ) =>
+            {
+                eprintln!("skipped temporal unit: {err}");
+            }
+            result => result?,
         }
-    }
 
-    fn temporal_unit(&mut self, b: &mut BitStream, size: u64) {
-        let mut sz = size;
-        while sz > 0 {
-            let frame_unit_size = b.leb128();
-            sz -= b.leb_128_bytes;
-            self.frame_unit(b, sz);
-            sz -= frame_unit_size;
+        while let Some(frame) = decoder.receive_frame() {
+            println!(
+                "frame pts={:?} presentation_time={:?} frame_type={} show_existing_frame={}",
+                frame.pts,
+                frame.presentation_time,
+                frame.header.frame_type(),
+                frame.header.show_existing_frame()
+            );
         }
     }
 
-    fn frame_unit(&mut self, b: &mut BitStream, size: u64) {
-        let mut sz = size;
-        while sz > 0 {
-            let obu_length = b.leb128();
-            sz -= b.leb_128_bytes;
-            let _obu = OpenBitstreamUnit::new(b, sz, &mut self.state);
-            sz -= obu_length;
-        }
-    }
+    Ok(())
 }
@@ -0,0 +1,126 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CdefParams {
+    pub(crate) cdef_damping: u64,
+    pub(crate) cdef_bits: u64,
+    pub(crate) cdef_y_pri_strength: Vec<u64>,
+    pub(crate) cdef_y_sec_strength: Vec<u64>,
+    pub(crate) cdef_uv_pri_strength: Vec<u64>,
+    pub(crate) cdef_uv_sec_strength: Vec<u64>,
+}
+
+impl Default for CdefParams {
+    /// CDEF disabled, with a single preset of zero strength
+    fn default() -> Self {
+        CdefParams {
+            cdef_damping: 3,
+            cdef_bits: 0,
+            cdef_y_pri_strength: vec![0],
+            cdef_y_sec_strength: vec![0],
+            cdef_uv_pri_strength: vec![0],
+            cdef_uv_sec_strength: vec![0],
+        }
+    }
+}
+
+impl CdefParams {
+    pub fn new(
+        b: &mut BitStream,
+        enable_cdef: bool,
+        coded_lossless: bool,
+        allow_intrabc: bool,
+        num_planes: u64,
+    ) -> Result<CdefParams> {
+        if coded_lossless || allow_intrabc || !enable_cdef {
+            return Ok(CdefParams::default());
+        }
+
+        let mut cp = CdefParams {
+            cdef_damping: b.f(2, "cdef_damping_minus_3")? + 3,
+            cdef_bits: b.f(2, "cdef_bits")?,
+            cdef_y_pri_strength: vec![],
+            cdef_y_sec_strength: vec![],
+            cdef_uv_pri_strength: vec![],
+            cdef_uv_sec_strength: vec![],
+        };
+
+        for _ in 0..(1 << cp.cdef_bits) {
+            cp.cdef_y_pri_strength.push(b.f(4, "cdef_y_pri_strength")?);
+            cp.cdef_y_sec_strength
+                .push(read_sec_strength(b, "cdef_y_sec_strength")?);
+
+            if num_planes > 1 {
+                cp.cdef_uv_pri_strength
+                    .push(b.f(4, "cdef_uv_pri_strength")?);
+                cp.cdef_uv_sec_strength
+                    .push(read_sec_strength(b, "cdef_uv_sec_strength")?);
+            }
+        }
+
+        Ok(cp)
+    }
+
+    pub fn cdef_damping(&self) -> u64 {
+        self.cdef_damping
+    }
+
+    pub fn cdef_bits(&self) -> u64 {
+        self.cdef_bits
+    }
+
+    /// Strengths of each of the `1 << cdef_bits` presets
+    pub fn cdef_y_pri_strength(&self) -> &[u64] {
+        &self.cdef_y_pri_strength
+    }
+
+    pub fn cdef_y_sec_strength(&self) -> &[u64] {
+        &self.cdef_y_sec_strength
+    }
+
+    pub fn cdef_uv_pri_strength(&self) -> &[u64] {
+        &self.cdef_uv_pri_strength
+    }
+
+    pub fn cdef_uv_sec_strength(&self) -> &[u64] {
+        &self.cdef_uv_sec_strength
+    }
+}
+
+/// A secondary strength of 3 is coded for a strength of 4
+fn read_sec_strength(b: &mut BitStream, element: &'static str) -> Result<u64> {
+    match b.f(2, element)? {
+        3 => Ok(4),
+        strength => Ok(strength),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CdefParams;
+    use crate::bits::bitstream::BitStream;
+
+    #[test]
+    fn cdef_disabled() {
+        let mut bs = BitStream::new(vec![0xff]);
+        let cp = CdefParams::new(&mut bs, false, false, false, 3).unwrap();
+
+        assert_eq!(CdefParams::default(), cp);
+        assert_eq!(0, bs.get_position());
+    }
+
+    #[test]
+    fn cdef_strengths() {
+        // Damping 5, two presets with y strengths 9/3 and 1/0, uv strengths 2/1 and 0/2
+        let mut bs = BitStream::new(vec![0b1001_1001, 0b1100_1001, 0b0001_0000, 0b0010_0000]);
+        let cp = CdefParams::new(&mut bs, true, false, false, 3).unwrap();
+
+        assert_eq!(5, cp.cdef_damping);
+        assert_eq!(1, cp.cdef_bits);
+        assert_eq!(vec![9, 1], cp.cdef_y_pri_strength);
+        assert_eq!(vec![4, 0], cp.cdef_y_sec_strength);
+        assert_eq!(vec![2, 0], cp.cdef_uv_pri_strength);
+        assert_eq!(vec![1, 2], cp.cdef_uv_sec_strength);
+        assert_eq!(28, bs.get_position());
+    }
+}
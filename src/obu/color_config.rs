@@ -1,18 +1,19 @@
-use crate::bits::bitstream::BitStream;
+use crate::{bits::bitstream::BitStream, error::Result};
 
-#[derive(Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ColorConfig {
-    high_bitdepth: bool,
-    twelve_bit: bool,
-    mono_chrome: bool,
-    color_primaries: u64,
-    transfer_characteristics: u64,
-    matrix_coefficients: u64,
-    color_range: bool,
-    subsampling_x: bool,
-    subsampling_y: bool,
-    chroma_sample_position: u64,
-    separate_uv_delta_q: bool,
+    pub(crate) bit_depth: u64,
+    pub(crate) high_bitdepth: bool,
+    pub(crate) twelve_bit: bool,
+    pub(crate) mono_chrome: bool,
+    pub(crate) color_primaries: u64,
+    pub(crate) transfer_characteristics: u64,
+    pub(crate) matrix_coefficients: u64,
+    pub(crate) color_range: bool,
+    pub(crate) subsampling_x: bool,
+    pub(crate) subsampling_y: bool,
+    pub(crate) chroma_sample_position: u64,
+    pub(crate) separate_uv_delta_q: bool,
 }
 
 impl ColorConfig {
@@ -21,14 +22,14 @@ impl ColorConfig {
         seq_profile: u64,
         bit_depth: &mut u64,
         num_planes: &mut u64,
-    ) -> ColorConfig {
+    ) -> Result<ColorConfig> {
         let mut cc = ColorConfig {
-            high_bitdepth: b.f(1) != 0,
+            high_bitdepth: b.f(1, "high_bitdepth")? != 0,
             ..Default::default()
         };
 
         if seq_profile == 2 && cc.high_bitdepth {
-            cc.twelve_bit = b.f(1) != 0;
+            cc.twelve_bit = b.f(1, "twelve_bit")? != 0;
             if cc.twelve_bit {
                 *bit_depth = 12;
             } else {
@@ -42,10 +43,12 @@ impl ColorConfig {
             }
         }
 
+        cc.bit_depth = *bit_depth;
+
         if seq_profile == 1 {
             cc.mono_chrome = false;
         } else {
-            cc.mono_chrome = b.f(1) != 0;
+            cc.mono_chrome = b.f(1, "mono_chrome")? != 0;
         }
 
         if cc.mono_chrome {
@@ -54,11 +57,11 @@ impl ColorConfig {
             *num_planes = 3;
         }
 
-        let color_description_present_flag = b.f(1) != 0;
+        let color_description_present_flag = b.f(1, "color_description_present_flag")? != 0;
         if color_description_present_flag {
-            cc.color_primaries = b.f(8);
-            cc.transfer_characteristics = b.f(8);
-            cc.matrix_coefficients = b.f(8);
+            cc.color_primaries = b.f(8, "color_primaries")?;
+            cc.transfer_characteristics = b.f(8, "transfer_characteristics")?;
+            cc.matrix_coefficients = b.f(8, "matrix_coefficients")?;
         } else {
             cc.color_primaries = CP_UNSPECIFIED;
             cc.transfer_characteristics = TC_UNSPECIFIED;
@@ -66,13 +69,13 @@ impl ColorConfig {
         }
 
         if cc.mono_chrome {
-            cc.color_range = b.f(1) != 0;
+            cc.color_range = b.f(1, "color_range")? != 0;
             cc.subsampling_x = true;
             cc.subsampling_y = true;
             cc.chroma_sample_position = CSP_UNKNOWN;
             cc.separate_uv_delta_q = false;
 
-            return cc;
+            return Ok(cc);
         } else if cc.color_primaries == CP_BT_709
             && cc.transfer_characteristics == TC_SRGB
             && cc.matrix_coefficients == MC_IDENTITY
@@ -81,7 +84,7 @@ impl ColorConfig {
             cc.subsampling_x = false;
             cc.subsampling_y = false;
         } else {
-            cc.color_range = b.f(1) != 0;
+            cc.color_range = b.f(1, "color_range")? != 0;
             if seq_profile == 0 {
                 cc.subsampling_x = true;
                 cc.subsampling_y = true;
@@ -89,9 +92,9 @@ impl ColorConfig {
                 cc.subsampling_x = false;
                 cc.subsampling_y = false;
             } else if *bit_depth == 12 {
-                cc.subsampling_x = b.f(1) != 0;
+                cc.subsampling_x = b.f(1, "subsampling_x")? != 0;
                 if cc.subsampling_x {
-                    cc.subsampling_y = b.f(1) != 0;
+                    cc.subsampling_y = b.f(1, "subsampling_y")? != 0;
                 } else {
                     cc.subsampling_y = false;
                 }
@@ -101,13 +104,61 @@ impl ColorConfig {
             }
 
             if cc.subsampling_x && cc.subsampling_y {
-                cc.chroma_sample_position = b.f(2);
+                cc.chroma_sample_position = b.f(2, "chroma_sample_position")?;
             }
         }
 
-        cc.separate_uv_delta_q = b.f(1) != 0;
+        cc.separate_uv_delta_q = b.f(1, "separate_uv_delta_q")? != 0;
+
+        Ok(cc)
+    }
+
+    pub fn bit_depth(&self) -> u64 {
+        self.bit_depth
+    }
+
+    pub fn high_bitdepth(&self) -> bool {
+        self.high_bitdepth
+    }
+
+    pub fn twelve_bit(&self) -> bool {
+        self.twelve_bit
+    }
+
+    pub fn mono_chrome(&self) -> bool {
+        self.mono_chrome
+    }
+
+    pub fn color_primaries(&self) -> u64 {
+        self.color_primaries
+    }
+
+    pub fn transfer_characteristics(&self) -> u64 {
+        self.transfer_characteristics
+    }
+
+    pub fn matrix_coefficients(&self) -> u64 {
+        self.matrix_coefficients
+    }
+
+    pub fn color_range(&self) -> bool {
+        self.color_range
+    }
+
+    pub fn subsampling_x(&self) -> bool {
+        self.subsampling_x
+    }
+
+    pub fn subsampling_y(&self) -> bool {
+        self.subsampling_y
+    }
+
+    pub fn chroma_sample_position(&self) -> u64 {
+        self.chroma_sample_position
+    }
 
-        cc
+    pub fn separate_uv_delta_q(&self) -> bool {
+        self.separate_uv_delta_q
     }
 }
 
@@ -1,25 +1,42 @@
-use crate::bits::bitstream::BitStream;
+use crate::{bits::bitstream::BitStream, error::Result};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct DecoderModelInfo {
-    pub buffer_delay_length_minus_1: u64,
-    pub num_units_in_decoding_tick: u64,
-    pub buffer_removal_time_length_minus_1: u64,
-    pub frame_presentation_time_length_minus_1: u64,
+    pub(crate) buffer_delay_length_minus_1: u64,
+    pub(crate) num_units_in_decoding_tick: u64,
+    pub(crate) buffer_removal_time_length_minus_1: u64,
+    pub(crate) frame_presentation_time_length_minus_1: u64,
 }
 
 impl DecoderModelInfo {
-    pub fn new(b: &mut BitStream) -> DecoderModelInfo {
-        let buffer_delay_length_minus_1 = b.f(5);
-        let num_units_in_decoding_tick = b.f(32);
-        let buffer_removal_time_length_minus_1 = b.f(5);
-        let frame_presentation_time_length_minus_1 = b.f(5);
+    pub fn new(b: &mut BitStream) -> Result<DecoderModelInfo> {
+        let buffer_delay_length_minus_1 = b.f(5, "buffer_delay_length_minus_1")?;
+        let num_units_in_decoding_tick = b.f(32, "num_units_in_decoding_tick")?;
+        let buffer_removal_time_length_minus_1 = b.f(5, "buffer_removal_time_length_minus_1")?;
+        let frame_presentation_time_length_minus_1 =
+            b.f(5, "frame_presentation_time_length_minus_1")?;
 
-        DecoderModelInfo {
+        Ok(DecoderModelInfo {
             buffer_delay_length_minus_1,
             num_units_in_decoding_tick,
             buffer_removal_time_length_minus_1,
             frame_presentation_time_length_minus_1,
-        }
+        })
+    }
+
+    pub fn buffer_delay_length_minus_1(&self) -> u64 {
+        self.buffer_delay_length_minus_1
+    }
+
+    pub fn num_units_in_decoding_tick(&self) -> u64 {
+        self.num_units_in_decoding_tick
+    }
+
+    pub fn buffer_removal_time_length_minus_1(&self) -> u64 {
+        self.buffer_removal_time_length_minus_1
+    }
+
+    pub fn frame_presentation_time_length_minus_1(&self) -> u64 {
+        self.frame_presentation_time_length_minus_1
     }
 }
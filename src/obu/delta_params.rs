@@ -0,0 +1,73 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeltaQParams {
+    pub(crate) delta_q_present: bool,
+    pub(crate) delta_q_res: u64,
+}
+
+impl DeltaQParams {
+    pub fn new(b: &mut BitStream, base_q_idx: u64) -> Result<DeltaQParams> {
+        let mut dqp = DeltaQParams::default();
+
+        if base_q_idx > 0 {
+            dqp.delta_q_present = b.f(1, "delta_q_present")? != 0;
+        }
+
+        if dqp.delta_q_present {
+            dqp.delta_q_res = b.f(2, "delta_q_res")?;
+        }
+
+        Ok(dqp)
+    }
+
+    pub fn delta_q_present(&self) -> bool {
+        self.delta_q_present
+    }
+
+    pub fn delta_q_res(&self) -> u64 {
+        self.delta_q_res
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeltaLfParams {
+    pub(crate) delta_lf_present: bool,
+    pub(crate) delta_lf_res: u64,
+    pub(crate) delta_lf_multi: bool,
+}
+
+impl DeltaLfParams {
+    pub fn new(
+        b: &mut BitStream,
+        delta_q_present: bool,
+        allow_intrabc: bool,
+    ) -> Result<DeltaLfParams> {
+        let mut dlp = DeltaLfParams::default();
+
+        if delta_q_present {
+            if !allow_intrabc {
+                dlp.delta_lf_present = b.f(1, "delta_lf_present")? != 0;
+            }
+
+            if dlp.delta_lf_present {
+                dlp.delta_lf_res = b.f(2, "delta_lf_res")?;
+                dlp.delta_lf_multi = b.f(1, "delta_lf_multi")? != 0;
+            }
+        }
+
+        Ok(dlp)
+    }
+
+    pub fn delta_lf_present(&self) -> bool {
+        self.delta_lf_present
+    }
+
+    pub fn delta_lf_res(&self) -> u64 {
+        self.delta_lf_res
+    }
+
+    pub fn delta_lf_multi(&self) -> bool {
+        self.delta_lf_multi
+    }
+}
@@ -0,0 +1,406 @@
+use crate::{bits::bitstream::BitStream, error::Result, State};
+
+use super::{
+    obu_frame_header::{INTER_FRAME, REFS_PER_FRAME},
+    obu_sequence_header::ObuSequenceHeader,
+};
+
+const MAX_NUM_Y_POINTS: u64 = 14;
+const MAX_NUM_CB_POINTS: u64 = 10;
+const MAX_NUM_CR_POINTS: u64 = 10;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FilmGrainParams {
+    pub(crate) apply_grain: bool,
+    pub(crate) grain_seed: u64,
+    pub(crate) update_grain: bool,
+    pub(crate) film_grain_params_ref_idx: usize,
+    pub(crate) point_y_value: Vec<u64>,
+    pub(crate) point_y_scaling: Vec<u64>,
+    pub(crate) chroma_scaling_from_luma: bool,
+    pub(crate) point_cb_value: Vec<u64>,
+    pub(crate) point_cb_scaling: Vec<u64>,
+    pub(crate) point_cr_value: Vec<u64>,
+    pub(crate) point_cr_scaling: Vec<u64>,
+    pub(crate) grain_scaling_minus_8: u64,
+    pub(crate) ar_coeff_lag: u64,
+    pub(crate) ar_coeffs_y_plus_128: Vec<u64>,
+    pub(crate) ar_coeffs_cb_plus_128: Vec<u64>,
+    pub(crate) ar_coeffs_cr_plus_128: Vec<u64>,
+    pub(crate) ar_coeff_shift_minus_6: u64,
+    pub(crate) grain_scale_shift: u64,
+    pub(crate) cb_mult: u64,
+    pub(crate) cb_luma_mult: u64,
+    pub(crate) cb_offset: u64,
+    pub(crate) cr_mult: u64,
+    pub(crate) cr_luma_mult: u64,
+    pub(crate) cr_offset: u64,
+    pub(crate) overlap_flag: bool,
+    pub(crate) clip_to_restricted_range: bool,
+}
+
+impl FilmGrainParams {
+    pub fn new(
+        b: &mut BitStream,
+        sh: &ObuSequenceHeader,
+        state: &State,
+        frame_type: u64,
+        show_frame: bool,
+        showable_frame: bool,
+        ref_frame_idx: &[usize; REFS_PER_FRAME],
+    ) -> Result<FilmGrainParams> {
+        if !sh.film_grain_params_present || (!show_frame && !showable_frame) {
+            return Ok(FilmGrainParams::default());
+        }
+
+        let apply_grain = b.f(1, "apply_grain")? != 0;
+        if !apply_grain {
+            return Ok(FilmGrainParams::default());
+        }
+
+        let grain_seed = b.f(16, "grain_seed")?;
+
+        let update_grain = match frame_type {
+            INTER_FRAME => b.f(1, "update_grain")? != 0,
+            _ => true,
+        };
+
+        if !update_grain {
+            let film_grain_params_ref_idx = b.f(3, "film_grain_params_ref_idx")? as usize;
+            if !ref_frame_idx.contains(&film_grain_params_ref_idx) {
+                return Err(b.invalid_value(
+                    "film_grain_params_ref_idx",
+                    film_grain_params_ref_idx as u64,
+                ));
+            }
+
+            // load_grain_params() keeps the seed of the current frame
+            return Ok(FilmGrainParams {
+                grain_seed,
+                update_grain,
+                film_grain_params_ref_idx,
+                ..state.ref_film_grain_params[film_grain_params_ref_idx].clone()
+            });
+        }
+
+        let mut fgp = FilmGrainParams {
+            apply_grain,
+            grain_seed,
+            update_grain,
+            ..Default::default()
+        };
+
+        let num_y_points = b.f(4, "num_y_points")?;
+        if num_y_points > MAX_NUM_Y_POINTS {
+            return Err(b.invalid_value("num_y_points", num_y_points));
+        }
+        (fgp.point_y_value, fgp.point_y_scaling) =
+            read_scaling_points(b, num_y_points, "point_y_value", "point_y_scaling")?;
+
+        let cc = &sh.color_config;
+        if cc.mono_chrome {
+            fgp.chroma_scaling_from_luma = false;
+        } else {
+            fgp.chroma_scaling_from_luma = b.f(1, "chroma_scaling_from_luma")? != 0;
+        }
+
+        let chroma_points_present = !(cc.mono_chrome
+            || fgp.chroma_scaling_from_luma
+            || (cc.subsampling_x && cc.subsampling_y && num_y_points == 0));
+
+        if chroma_points_present {
+            let num_cb_points = b.f(4, "num_cb_points")?;
+            if num_cb_points > MAX_NUM_CB_POINTS {
+                return Err(b.invalid_value("num_cb_points", num_cb_points));
+            }
+            (fgp.point_cb_value, fgp.point_cb_scaling) =
+                read_scaling_points(b, num_cb_points, "point_cb_value", "point_cb_scaling")?;
+
+            let num_cr_points = b.f(4, "num_cr_points")?;
+            if num_cr_points > MAX_NUM_CR_POINTS {
+                return Err(b.invalid_value("num_cr_points", num_cr_points));
+            }
+            (fgp.point_cr_value, fgp.point_cr_scaling) =
+                read_scaling_points(b, num_cr_points, "point_cr_value", "point_cr_scaling")?;
+        }
+
+        fgp.grain_scaling_minus_8 = b.f(2, "grain_scaling_minus_8")?;
+        fgp.ar_coeff_lag = b.f(2, "ar_coeff_lag")?;
+
+        let num_pos_luma = 2 * fgp.ar_coeff_lag * (fgp.ar_coeff_lag + 1);
+        let num_pos_chroma = if num_y_points > 0 {
+            for _ in 0..num_pos_luma {
+                fgp.ar_coeffs_y_plus_128
+                    .push(b.f(8, "ar_coeffs_y_plus_128")?);
+            }
+            num_pos_luma + 1
+        } else {
+            num_pos_luma
+        };
+
+        if fgp.chroma_scaling_from_luma || !fgp.point_cb_value.is_empty() {
+            for _ in 0..num_pos_chroma {
+                fgp.ar_coeffs_cb_plus_128
+                    .push(b.f(8, "ar_coeffs_cb_plus_128")?);
+            }
+        }
+
+        if fgp.chroma_scaling_from_luma || !fgp.point_cr_value.is_empty() {
+            for _ in 0..num_pos_chroma {
+                fgp.ar_coeffs_cr_plus_128
+                    .push(b.f(8, "ar_coeffs_cr_plus_128")?);
+            }
+        }
+
+        fgp.ar_coeff_shift_minus_6 = b.f(2, "ar_coeff_shift_minus_6")?;
+        fgp.grain_scale_shift = b.f(2, "grain_scale_shift")?;
+
+        if !fgp.point_cb_value.is_empty() {
+            fgp.cb_mult = b.f(8, "cb_mult")?;
+            fgp.cb_luma_mult = b.f(8, "cb_luma_mult")?;
+            fgp.cb_offset = b.f(9, "cb_offset")?;
+        }
+
+        if !fgp.point_cr_value.is_empty() {
+            fgp.cr_mult = b.f(8, "cr_mult")?;
+            fgp.cr_luma_mult = b.f(8, "cr_luma_mult")?;
+            fgp.cr_offset = b.f(9, "cr_offset")?;
+        }
+
+        fgp.overlap_flag = b.f(1, "overlap_flag")? != 0;
+        fgp.clip_to_restricted_range = b.f(1, "clip_to_restricted_range")? != 0;
+
+        Ok(fgp)
+    }
+
+    pub fn apply_grain(&self) -> bool {
+        self.apply_grain
+    }
+
+    pub fn grain_seed(&self) -> u64 {
+        self.grain_seed
+    }
+
+    pub fn update_grain(&self) -> bool {
+        self.update_grain
+    }
+
+    pub fn film_grain_params_ref_idx(&self) -> usize {
+        self.film_grain_params_ref_idx
+    }
+
+    pub fn point_y_value(&self) -> &[u64] {
+        &self.point_y_value
+    }
+
+    pub fn point_y_scaling(&self) -> &[u64] {
+        &self.point_y_scaling
+    }
+
+    pub fn chroma_scaling_from_luma(&self) -> bool {
+        self.chroma_scaling_from_luma
+    }
+
+    pub fn point_cb_value(&self) -> &[u64] {
+        &self.point_cb_value
+    }
+
+    pub fn point_cb_scaling(&self) -> &[u64] {
+        &self.point_cb_scaling
+    }
+
+    pub fn point_cr_value(&self) -> &[u64] {
+        &self.point_cr_value
+    }
+
+    pub fn point_cr_scaling(&self) -> &[u64] {
+        &self.point_cr_scaling
+    }
+
+    pub fn grain_scaling_minus_8(&self) -> u64 {
+        self.grain_scaling_minus_8
+    }
+
+    pub fn ar_coeff_lag(&self) -> u64 {
+        self.ar_coeff_lag
+    }
+
+    pub fn ar_coeffs_y_plus_128(&self) -> &[u64] {
+        &self.ar_coeffs_y_plus_128
+    }
+
+    pub fn ar_coeffs_cb_plus_128(&self) -> &[u64] {
+        &self.ar_coeffs_cb_plus_128
+    }
+
+    pub fn ar_coeffs_cr_plus_128(&self) -> &[u64] {
+        &self.ar_coeffs_cr_plus_128
+    }
+
+    pub fn ar_coeff_shift_minus_6(&self) -> u64 {
+        self.ar_coeff_shift_minus_6
+    }
+
+    pub fn grain_scale_shift(&self) -> u64 {
+        self.grain_scale_shift
+    }
+
+    pub fn cb_mult(&self) -> u64 {
+        self.cb_mult
+    }
+
+    pub fn cb_luma_mult(&self) -> u64 {
+        self.cb_luma_mult
+    }
+
+    pub fn cb_offset(&self) -> u64 {
+        self.cb_offset
+    }
+
+    pub fn cr_mult(&self) -> u64 {
+        self.cr_mult
+    }
+
+    pub fn cr_luma_mult(&self) -> u64 {
+        self.cr_luma_mult
+    }
+
+    pub fn cr_offset(&self) -> u64 {
+        self.cr_offset
+    }
+
+    pub fn overlap_flag(&self) -> bool {
+        self.overlap_flag
+    }
+
+    pub fn clip_to_restricted_range(&self) -> bool {
+        self.clip_to_restricted_range
+    }
+}
+
+fn read_scaling_points(
+    b: &mut BitStream,
+    num_points: u64,
+    value_element: &'static str,
+    scaling_element: &'static str,
+) -> Result<(Vec<u64>, Vec<u64>)> {
+    let mut values: Vec<u64> = vec![];
+    let mut scalings = vec![];
+
+    for _ in 0..num_points {
+        let value = b.f(8, value_element)?;
+        if values.last().is_some_and(|&last| value <= last) {
+            return Err(b.invalid_value(value_element, value));
+        }
+
+        values.push(value);
+        scalings.push(b.f(8, scaling_element)?);
+    }
+
+    Ok((values, scalings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilmGrainParams;
+    use crate::{
+        bits::bitstream::BitStream,
+        error::{CarpError, ErrorKind},
+        obu::{
+            obu_frame_header::{INTER_FRAME, KEY_FRAME},
+            obu_sequence_header::ObuSequenceHeader,
+        },
+        test_util::sequence_header,
+        State,
+    };
+
+    #[test]
+    fn film_grain_params() {
+        let sh = ObuSequenceHeader {
+            film_grain_params_present: true,
+            ..sequence_header(352, 288)
+        };
+        let state = State::default();
+
+        // apply_grain with seed 0x1234, one y point (64, 80), chroma_scaling_from_luma,
+        // grain_scaling_minus_8 2, ar_coeff_lag 0, one cb and cr coefficient,
+        // ar_coeff_shift_minus_6 1, grain_scale_shift 0, overlap_flag
+        let mut bs = BitStream::new(vec![
+            0b1000_1001,
+            0b0001_1010,
+            0b0000_1010,
+            0b0000_0010,
+            0b1000_0110,
+            0b0010_0000,
+            0b0001_1111,
+            0b1101_0010,
+        ]);
+        let fgp =
+            FilmGrainParams::new(&mut bs, &sh, &state, KEY_FRAME, true, false, &[0; 7]).unwrap();
+
+        assert!(fgp.apply_grain);
+        assert!(fgp.update_grain);
+        assert_eq!(0x1234, fgp.grain_seed);
+        assert_eq!(
+            (vec![64], vec![80]),
+            (fgp.point_y_value, fgp.point_y_scaling)
+        );
+        assert!(fgp.chroma_scaling_from_luma);
+        assert!(fgp.point_cb_value.is_empty());
+        assert_eq!(2, fgp.grain_scaling_minus_8);
+        assert!(fgp.ar_coeffs_y_plus_128.is_empty());
+        assert_eq!(vec![128], fgp.ar_coeffs_cb_plus_128);
+        assert_eq!(vec![127], fgp.ar_coeffs_cr_plus_128);
+        assert_eq!(1, fgp.ar_coeff_shift_minus_6);
+        assert!(fgp.overlap_flag);
+        assert!(!fgp.clip_to_restricted_range);
+        assert_eq!(64, bs.get_position());
+    }
+
+    #[test]
+    fn load_grain_params() {
+        let sh = ObuSequenceHeader {
+            film_grain_params_present: true,
+            ..sequence_header(352, 288)
+        };
+        let mut state = State::default();
+        state.ref_film_grain_params[3] = FilmGrainParams {
+            apply_grain: true,
+            grain_seed: 1,
+            update_grain: true,
+            point_y_value: vec![10, 20],
+            point_y_scaling: vec![30, 40],
+            ..Default::default()
+        };
+
+        // apply_grain with seed 0xabcd, no update_grain, film_grain_params_ref_idx 3
+        let mut bs = BitStream::new(vec![0b1101_0101, 0b1110_0110, 0b1001_1000]);
+        let fgp = FilmGrainParams::new(
+            &mut bs,
+            &sh,
+            &state,
+            INTER_FRAME,
+            true,
+            false,
+            &[0, 1, 2, 3, 4, 5, 6],
+        )
+        .unwrap();
+
+        assert_eq!(0xabcd, fgp.grain_seed);
+        assert!(!fgp.update_grain);
+        assert_eq!(3, fgp.film_grain_params_ref_idx);
+        assert_eq!(vec![10, 20], fgp.point_y_value);
+        assert_eq!(21, bs.get_position());
+
+        // The reference slot has to be used by the frame
+        let mut bs = BitStream::new(vec![0b1101_0101, 0b1110_0110, 0b1001_1000]);
+        assert_eq!(
+            Some(CarpError::new(
+                21,
+                "film_grain_params_ref_idx",
+                ErrorKind::InvalidValue(3)
+            )),
+            FilmGrainParams::new(&mut bs, &sh, &state, INTER_FRAME, true, false, &[0; 7]).err()
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{
+    bits::bitstream::BitStream,
+    error::{ErrorKind, Result},
+};
+
+use super::{obu_frame_header::REFS_PER_FRAME, obu_sequence_header::ObuSequenceHeader};
+
+const SUPERRES_NUM: u64 = 8;
+const SUPERRES_DENOM_MIN: u64 = 9;
+const SUPERRES_DENOM_BITS: u64 = 3;
+
+/// Frame dimensions from frame_size(), superres_params() and render_size()
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameSize {
+    pub(crate) frame_width: u64,
+    pub(crate) frame_height: u64,
+    pub(crate) upscaled_width: u64,
+    pub(crate) render_width: u64,
+    pub(crate) render_height: u64,
+    pub(crate) use_superres: bool,
+    pub(crate) superres_denom: u64,
+    pub(crate) mi_cols: u64,
+    pub(crate) mi_rows: u64,
+}
+
+impl FrameSize {
+    /// Reads frame_size() and render_size()
+    pub fn new(
+        b: &mut BitStream,
+        sh: &ObuSequenceHeader,
+        frame_size_override_flag: bool,
+    ) -> Result<FrameSize> {
+        let mut fs = FrameSize::default();
+
+        if frame_size_override_flag {
+            fs.frame_width = b.f(sh.frame_width_bits_minus_1 + 1, "frame_width_minus_1")? + 1;
+            fs.frame_height = b.f(sh.frame_height_bits_minus_1 + 1, "frame_height_minus_1")? + 1;
+
+            if fs.frame_width > sh.max_frame_width_minus_1 + 1 {
+                return Err(b.invalid_value("frame_width_minus_1", fs.frame_width - 1));
+            }
+            if fs.frame_height > sh.max_frame_height_minus_1 + 1 {
+                return Err(b.invalid_value("frame_height_minus_1", fs.frame_height - 1));
+            }
+        } else {
+            fs.frame_width = sh.max_frame_width_minus_1 + 1;
+            fs.frame_height = sh.max_frame_height_minus_1 + 1;
+        }
+
+        fs.superres_params(b, sh)?;
+        fs.compute_image_size();
+        fs.render_size(b)?;
+
+        Ok(fs)
+    }
+
+    /// Reads frame_size_with_refs(), where the size can be copied from one of the references.
+    ///
+    /// `ref_frame_sizes` are the sizes of the frames referenced by `ref_frame_idx`, `None` if
+    /// the reference slot is not valid.
+    pub fn with_refs(
+        b: &mut BitStream,
+        sh: &ObuSequenceHeader,
+        frame_size_override_flag: bool,
+        ref_frame_sizes: &[Option<FrameSize>; REFS_PER_FRAME],
+    ) -> Result<FrameSize> {
+        for ref_frame_size in ref_frame_sizes {
+            let found_ref = b.f(1, "found_ref")? != 0;
+
+            if found_ref {
+                let ref_frame_size = ref_frame_size
+                    .filter(|fs| fs.upscaled_width != 0 && fs.frame_height != 0)
+                    .ok_or_else(|| b.error("found_ref", ErrorKind::FrameLoss))?;
+
+                let mut fs = FrameSize {
+                    upscaled_width: ref_frame_size.upscaled_width,
+                    frame_width: ref_frame_size.upscaled_width,
+                    frame_height: ref_frame_size.frame_height,
+                    render_width: ref_frame_size.render_width,
+                    render_height: ref_frame_size.render_height,
+                    ..Default::default()
+                };
+
+                fs.superres_params(b, sh)?;
+                fs.compute_image_size();
+
+                return Ok(fs);
+            }
+        }
+
+        FrameSize::new(b, sh, frame_size_override_flag)
+    }
+
+    fn superres_params(&mut self, b: &mut BitStream, sh: &ObuSequenceHeader) -> Result<()> {
+        if sh.enable_superres {
+            self.use_superres = b.f(1, "use_superres")? != 0;
+        } else {
+            self.use_superres = false;
+        }
+
+        if self.use_superres {
+            let coded_denom = b.f(SUPERRES_DENOM_BITS, "coded_denom")?;
+            self.superres_denom = coded_denom + SUPERRES_DENOM_MIN;
+        } else {
+            self.superres_denom = SUPERRES_NUM;
+        }
+
+        self.upscaled_width = self.frame_width;
+        self.frame_width =
+            (self.upscaled_width * SUPERRES_NUM + (self.superres_denom / 2)) / self.superres_denom;
+
+        Ok(())
+    }
+
+    fn compute_image_size(&mut self) {
+        self.mi_cols = 2 * ((self.frame_width + 7) >> 3);
+        self.mi_rows = 2 * ((self.frame_height + 7) >> 3);
+    }
+
+    fn render_size(&mut self, b: &mut BitStream) -> Result<()> {
+        let render_and_frame_size_different = b.f(1, "render_and_frame_size_different")? != 0;
+
+        if render_and_frame_size_different {
+            self.render_width = b.f(16, "render_width_minus_1")? + 1;
+            self.render_height = b.f(16, "render_height_minus_1")? + 1;
+        } else {
+            self.render_width = self.upscaled_width;
+            self.render_height = self.frame_height;
+        }
+
+        Ok(())
+    }
+
+    /// Width of the coded frame, after superres downscaling
+    pub fn frame_width(&self) -> u64 {
+        self.frame_width
+    }
+
+    pub fn frame_height(&self) -> u64 {
+        self.frame_height
+    }
+
+    /// Width of the frame after superres upscaling
+    pub fn upscaled_width(&self) -> u64 {
+        self.upscaled_width
+    }
+
+    pub fn render_width(&self) -> u64 {
+        self.render_width
+    }
+
+    pub fn render_height(&self) -> u64 {
+        self.render_height
+    }
+
+    pub fn use_superres(&self) -> bool {
+        self.use_superres
+    }
+
+    pub fn superres_denom(&self) -> u64 {
+        self.superres_denom
+    }
+
+    /// Width and height of the frame in units of 4x4 luma samples
+    pub fn mi_cols(&self) -> u64 {
+        self.mi_cols
+    }
+
+    pub fn mi_rows(&self) -> u64 {
+        self.mi_rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameSize;
+    use crate::{
+        bits::bitstream::BitStream, obu::obu_sequence_header::ObuSequenceHeader,
+        test_util::sequence_header,
+    };
+
+    #[test]
+    fn frame_size() {
+        let sh = ObuSequenceHeader {
+            enable_superres: true,
+            ..sequence_header(1920, 1080)
+        };
+
+        // No superres and render size equals frame size
+        let mut bs = BitStream::new(vec![0b0000_0000]);
+        let fs = FrameSize::new(&mut bs, &sh, false).unwrap();
+
+        assert_eq!(1920, fs.frame_width);
+        assert_eq!(1920, fs.upscaled_width);
+        assert_eq!(1080, fs.frame_height);
+        assert_eq!(480, fs.mi_cols);
+        assert_eq!(270, fs.mi_rows);
+        assert_eq!((1920, 1080), (fs.render_width, fs.render_height));
+        assert_eq!(2, bs.get_position());
+    }
+
+    #[test]
+    fn frame_size_with_superres() {
+        let sh = ObuSequenceHeader {
+            enable_superres: true,
+            ..sequence_header(1920, 1080)
+        };
+
+        // 1279x719 frame, use_superres with coded_denom 7, render_and_frame_size_different
+        // with a 1280x720 render size
+        let mut bs = BitStream::new(vec![
+            0b1001_1111,
+            0b1100_1011,
+            0b0011_1011,
+            0b1110_0000,
+            0b1001_1111,
+            0b1110_0000,
+            0b0101_1001,
+            0b1110_0000,
+        ]);
+        let fs = FrameSize::new(&mut bs, &sh, true).unwrap();
+
+        assert_eq!(1279, fs.upscaled_width);
+        assert_eq!(16, fs.superres_denom);
+        assert_eq!(640, fs.frame_width);
+        assert_eq!(719, fs.frame_height);
+        assert_eq!(160, fs.mi_cols);
+        assert_eq!(180, fs.mi_rows);
+        assert_eq!((1280, 720), (fs.render_width, fs.render_height));
+        assert_eq!(59, bs.get_position());
+    }
+
+    #[test]
+    fn frame_size_with_refs() {
+        let sh = ObuSequenceHeader {
+            enable_superres: true,
+            ..sequence_header(1920, 1080)
+        };
+        let mut ref_frame_sizes = [None; 7];
+        ref_frame_sizes[2] = Some(FrameSize {
+            upscaled_width: 640,
+            frame_width: 640,
+            frame_height: 360,
+            render_width: 640,
+            render_height: 360,
+            ..Default::default()
+        });
+
+        // found_ref for the third reference, no superres
+        let mut bs = BitStream::new(vec![0b0010_0000]);
+        let fs = FrameSize::with_refs(&mut bs, &sh, true, &ref_frame_sizes).unwrap();
+
+        assert_eq!(640, fs.frame_width);
+        assert_eq!(360, fs.frame_height);
+        assert_eq!(160, fs.mi_cols);
+        assert_eq!(90, fs.mi_rows);
+        assert_eq!(4, bs.get_position());
+    }
+}
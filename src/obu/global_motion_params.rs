@@ -0,0 +1,236 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+use super::obu_frame_header::{ALTREF_FRAME, LAST_FRAME, TOTAL_REFS_PER_FRAME};
+
+pub const WARPEDMODEL_PREC_BITS: u64 = 16;
+
+const GM_ABS_TRANS_BITS: u64 = 12;
+const GM_ABS_TRANS_ONLY_BITS: u64 = 9;
+const GM_ABS_ALPHA_BITS: u64 = 12;
+const GM_ALPHA_PREC_BITS: u64 = 15;
+const GM_TRANS_PREC_BITS: u64 = 6;
+const GM_TRANS_ONLY_PREC_BITS: u64 = 3;
+
+const DEFAULT_GM_PARAMS: [i64; 6] = [
+    0,
+    0,
+    1 << WARPEDMODEL_PREC_BITS,
+    0,
+    0,
+    1 << WARPEDMODEL_PREC_BITS,
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub enum GmType {
+    #[default]
+    Identity,
+    Translation,
+    RotZoom,
+    Affine,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobalMotionParams {
+    pub(crate) gm_type: [GmType; TOTAL_REFS_PER_FRAME],
+    pub(crate) gm_params: [[i64; 6]; TOTAL_REFS_PER_FRAME],
+}
+
+impl Default for GlobalMotionParams {
+    fn default() -> Self {
+        GlobalMotionParams {
+            gm_type: [GmType::Identity; TOTAL_REFS_PER_FRAME],
+            gm_params: [DEFAULT_GM_PARAMS; TOTAL_REFS_PER_FRAME],
+        }
+    }
+}
+
+impl GlobalMotionParams {
+    pub fn new(
+        b: &mut BitStream,
+        frame_is_intra: bool,
+        allow_high_precision_mv: bool,
+        previous: &GlobalMotionParams,
+    ) -> Result<GlobalMotionParams> {
+        let mut gmp = GlobalMotionParams::default();
+
+        if frame_is_intra {
+            return Ok(gmp);
+        }
+
+        for r in LAST_FRAME..=ALTREF_FRAME {
+            let is_global = b.f(1, "is_global")? != 0;
+
+            let gm_type = if is_global {
+                let is_rot_zoom = b.f(1, "is_rot_zoom")? != 0;
+                if is_rot_zoom {
+                    GmType::RotZoom
+                } else {
+                    let is_translation = b.f(1, "is_translation")? != 0;
+                    match is_translation {
+                        true => GmType::Translation,
+                        false => GmType::Affine,
+                    }
+                }
+            } else {
+                GmType::Identity
+            };
+            gmp.gm_type[r] = gm_type;
+
+            let prev = &previous.gm_params[r];
+            let params = &mut gmp.gm_params[r];
+
+            if gm_type >= GmType::RotZoom {
+                params[2] = read_global_param(b, gm_type, 2, prev, allow_high_precision_mv)?;
+                params[3] = read_global_param(b, gm_type, 3, prev, allow_high_precision_mv)?;
+
+                if gm_type == GmType::Affine {
+                    params[4] = read_global_param(b, gm_type, 4, prev, allow_high_precision_mv)?;
+                    params[5] = read_global_param(b, gm_type, 5, prev, allow_high_precision_mv)?;
+                } else {
+                    params[4] = -params[3];
+                    params[5] = params[2];
+                }
+            }
+
+            if gm_type >= GmType::Translation {
+                params[0] = read_global_param(b, gm_type, 0, prev, allow_high_precision_mv)?;
+                params[1] = read_global_param(b, gm_type, 1, prev, allow_high_precision_mv)?;
+            }
+        }
+
+        Ok(gmp)
+    }
+
+    pub fn gm_type(&self) -> [GmType; TOTAL_REFS_PER_FRAME] {
+        self.gm_type
+    }
+
+    pub fn gm_params(&self) -> [[i64; 6]; TOTAL_REFS_PER_FRAME] {
+        self.gm_params
+    }
+}
+
+fn read_global_param(
+    b: &mut BitStream,
+    gm_type: GmType,
+    idx: usize,
+    prev_gm_params: &[i64; 6],
+    allow_high_precision_mv: bool,
+) -> Result<i64> {
+    let (abs_bits, prec_bits) = match idx {
+        0 | 1 if gm_type == GmType::Translation => (
+            GM_ABS_TRANS_ONLY_BITS - !allow_high_precision_mv as u64,
+            GM_TRANS_ONLY_PREC_BITS - !allow_high_precision_mv as u64,
+        ),
+        0 | 1 => (GM_ABS_TRANS_BITS, GM_TRANS_PREC_BITS),
+        _ => (GM_ABS_ALPHA_BITS, GM_ALPHA_PREC_BITS),
+    };
+
+    let prec_diff = WARPEDMODEL_PREC_BITS - prec_bits;
+    let (round, sub) = match idx % 3 {
+        2 => (1 << WARPEDMODEL_PREC_BITS, 1 << prec_bits),
+        _ => (0, 0),
+    };
+
+    let mx = 1 << abs_bits;
+    let r = (prev_gm_params[idx] >> prec_diff) - sub;
+
+    Ok((decode_signed_subexp_with_ref(b, -mx, mx + 1, r)? << prec_diff) + round)
+}
+
+fn decode_signed_subexp_with_ref(b: &mut BitStream, low: i64, high: i64, r: i64) -> Result<i64> {
+    let x = decode_unsigned_subexp_with_ref(b, (high - low) as u64, (r - low) as u64)?;
+
+    Ok(x as i64 + low)
+}
+
+fn decode_unsigned_subexp_with_ref(b: &mut BitStream, mx: u64, r: u64) -> Result<u64> {
+    let v = decode_subexp(b, mx)?;
+
+    match (r << 1) <= mx {
+        true => Ok(inverse_recenter(r, v)),
+        false => Ok(mx - 1 - inverse_recenter(mx - 1 - r, v)),
+    }
+}
+
+fn decode_subexp(b: &mut BitStream, num_syms: u64) -> Result<u64> {
+    let mut i = 0;
+    let mut mk = 0;
+    let k = 3;
+
+    loop {
+        let b2 = match i {
+            0 => k,
+            _ => k + i - 1,
+        };
+        let a = 1 << b2;
+
+        if num_syms <= mk + 3 * a {
+            return Ok(b.ns(num_syms - mk, "subexp_final_bits")? + mk);
+        }
+
+        if b.f(1, "subexp_more_bits")? == 0 {
+            return Ok(b.f(b2, "subexp_bits")? + mk);
+        }
+
+        i += 1;
+        mk += a;
+    }
+}
+
+fn inverse_recenter(r: u64, v: u64) -> u64 {
+    if v > 2 * r {
+        v
+    } else if v & 1 == 1 {
+        r - ((v + 1) >> 1)
+    } else {
+        r + (v >> 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GlobalMotionParams, GmType, DEFAULT_GM_PARAMS};
+    use crate::{
+        bits::bitstream::BitStream,
+        obu::obu_frame_header::{ALTREF_FRAME, LAST2_FRAME, LAST_FRAME},
+    };
+
+    #[test]
+    fn intra_frame() {
+        let mut bs = BitStream::new(vec![0xff]);
+        let gmp =
+            GlobalMotionParams::new(&mut bs, true, false, &GlobalMotionParams::default()).unwrap();
+
+        assert_eq!(GlobalMotionParams::default(), gmp);
+        assert_eq!(0, bs.get_position());
+    }
+
+    #[test]
+    fn global_motion_params() {
+        // Rotation and zoom for LAST_FRAME, translation for LAST2_FRAME, identity for the rest
+        let mut bs = BitStream::new(vec![
+            0b1111_0010,
+            0b0011_1010,
+            0b0001_1101,
+            0b1001_0010,
+            0b1000_0000,
+        ]);
+        let gmp =
+            GlobalMotionParams::new(&mut bs, false, true, &GlobalMotionParams::default()).unwrap();
+
+        assert_eq!(GmType::RotZoom, gmp.gm_type[LAST_FRAME]);
+        assert_eq!(
+            [2048, -2048, 65556, -8, 8, 65556],
+            gmp.gm_params[LAST_FRAME]
+        );
+        assert_eq!(GmType::Translation, gmp.gm_type[LAST2_FRAME]);
+        assert_eq!(
+            [40960, -24576, 65536, 0, 0, 65536],
+            gmp.gm_params[LAST2_FRAME]
+        );
+        assert_eq!(GmType::Identity, gmp.gm_type[ALTREF_FRAME]);
+        assert_eq!(DEFAULT_GM_PARAMS, gmp.gm_params[ALTREF_FRAME]);
+        assert_eq!(38, bs.get_position());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+use super::obu_frame_header::{
+    ALTREF2_FRAME, ALTREF_FRAME, GOLDEN_FRAME, INTRA_FRAME, TOTAL_REFS_PER_FRAME,
+};
+
+/// Reference deltas set up by setup_past_independence()
+const DEFAULT_REF_DELTAS: [i64; TOTAL_REFS_PER_FRAME] = {
+    let mut deltas = [0; TOTAL_REFS_PER_FRAME];
+    deltas[INTRA_FRAME] = 1;
+    deltas[GOLDEN_FRAME] = -1;
+    deltas[ALTREF_FRAME] = -1;
+    deltas[ALTREF2_FRAME] = -1;
+    deltas
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoopFilterParams {
+    pub(crate) loop_filter_level: [u64; 4],
+    pub(crate) loop_filter_sharpness: u64,
+    pub(crate) loop_filter_delta_enabled: bool,
+    pub(crate) loop_filter_delta_update: bool,
+    pub(crate) loop_filter_ref_deltas: [i64; TOTAL_REFS_PER_FRAME],
+    pub(crate) loop_filter_mode_deltas: [i64; 2],
+}
+
+impl Default for LoopFilterParams {
+    fn default() -> Self {
+        LoopFilterParams {
+            loop_filter_level: [0; 4],
+            loop_filter_sharpness: 0,
+            loop_filter_delta_enabled: false,
+            loop_filter_delta_update: false,
+            loop_filter_ref_deltas: DEFAULT_REF_DELTAS,
+            loop_filter_mode_deltas: [0; 2],
+        }
+    }
+}
+
+impl LoopFilterParams {
+    /// Reads loop_filter_params().
+    ///
+    /// `previous` holds the deltas of the primary reference frame, they are kept unless
+    /// the frame updates them.
+    pub fn new(
+        b: &mut BitStream,
+        coded_lossless: bool,
+        allow_intrabc: bool,
+        num_planes: u64,
+        previous: &LoopFilterParams,
+    ) -> Result<LoopFilterParams> {
+        if coded_lossless || allow_intrabc {
+            return Ok(LoopFilterParams::default());
+        }
+
+        let mut lfp = LoopFilterParams {
+            loop_filter_ref_deltas: previous.loop_filter_ref_deltas,
+            loop_filter_mode_deltas: previous.loop_filter_mode_deltas,
+            ..Default::default()
+        };
+
+        lfp.loop_filter_level[0] = b.f(6, "loop_filter_level")?;
+        lfp.loop_filter_level[1] = b.f(6, "loop_filter_level")?;
+        if num_planes > 1 && (lfp.loop_filter_level[0] != 0 || lfp.loop_filter_level[1] != 0) {
+            lfp.loop_filter_level[2] = b.f(6, "loop_filter_level")?;
+            lfp.loop_filter_level[3] = b.f(6, "loop_filter_level")?;
+        }
+
+        lfp.loop_filter_sharpness = b.f(3, "loop_filter_sharpness")?;
+        lfp.loop_filter_delta_enabled = b.f(1, "loop_filter_delta_enabled")? != 0;
+
+        if lfp.loop_filter_delta_enabled {
+            lfp.loop_filter_delta_update = b.f(1, "loop_filter_delta_update")? != 0;
+
+            if lfp.loop_filter_delta_update {
+                for delta in lfp.loop_filter_ref_deltas.iter_mut() {
+                    if b.f(1, "update_ref_delta")? != 0 {
+                        *delta = b.su(1 + 6, "loop_filter_ref_deltas")?;
+                    }
+                }
+
+                for delta in lfp.loop_filter_mode_deltas.iter_mut() {
+                    if b.f(1, "update_mode_delta")? != 0 {
+                        *delta = b.su(1 + 6, "loop_filter_mode_deltas")?;
+                    }
+                }
+            }
+        }
+
+        Ok(lfp)
+    }
+
+    /// Vertical and horizontal luma levels, followed by the U and V levels
+    pub fn loop_filter_level(&self) -> [u64; 4] {
+        self.loop_filter_level
+    }
+
+    pub fn loop_filter_sharpness(&self) -> u64 {
+        self.loop_filter_sharpness
+    }
+
+    pub fn loop_filter_delta_enabled(&self) -> bool {
+        self.loop_filter_delta_enabled
+    }
+
+    pub fn loop_filter_delta_update(&self) -> bool {
+        self.loop_filter_delta_update
+    }
+
+    pub fn loop_filter_ref_deltas(&self) -> [i64; TOTAL_REFS_PER_FRAME] {
+        self.loop_filter_ref_deltas
+    }
+
+    pub fn loop_filter_mode_deltas(&self) -> [i64; 2] {
+        self.loop_filter_mode_deltas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoopFilterParams, DEFAULT_REF_DELTAS};
+    use crate::{
+        bits::bitstream::BitStream,
+        obu::obu_frame_header::{GOLDEN_FRAME, LAST_FRAME},
+    };
+
+    #[test]
+    fn lossless() {
+        let mut bs = BitStream::new(vec![0xff]);
+        let lfp =
+            LoopFilterParams::new(&mut bs, true, false, 3, &LoopFilterParams::default()).unwrap();
+
+        assert_eq!([0; 4], lfp.loop_filter_level);
+        assert_eq!(DEFAULT_REF_DELTAS, lfp.loop_filter_ref_deltas);
+        assert_eq!(0, bs.get_position());
+    }
+
+    #[test]
+    fn delta_update() {
+        let mut previous = LoopFilterParams::default();
+        previous.loop_filter_ref_deltas[GOLDEN_FRAME] = 5;
+
+        // Levels 10, 20, 30 and 40, sharpness 2, delta update of the LAST_FRAME delta to -3
+        // and the second mode delta to 1
+        let mut bs = BitStream::new(vec![
+            0b0010_1001,
+            0b0100_0111,
+            0b1010_1000,
+            0b0101_1011,
+            0b1111_0100,
+            0b0000_0100,
+            0b0000_1000,
+        ]);
+        let lfp = LoopFilterParams::new(&mut bs, false, false, 3, &previous).unwrap();
+
+        assert_eq!([10, 20, 30, 40], lfp.loop_filter_level);
+        assert_eq!(2, lfp.loop_filter_sharpness);
+        assert!(lfp.loop_filter_delta_update);
+        assert_eq!(-3, lfp.loop_filter_ref_deltas[LAST_FRAME]);
+        assert_eq!(5, lfp.loop_filter_ref_deltas[GOLDEN_FRAME]);
+        assert_eq!([0, 1], lfp.loop_filter_mode_deltas);
+        assert_eq!(53, bs.get_position());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+use super::obu_sequence_header::ObuSequenceHeader;
+
+const RESTORATION_TILESIZE_MAX: u64 = 256;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FrameRestorationType {
+    #[default]
+    None,
+    Wiener,
+    Sgrproj,
+    Switchable,
+}
+
+impl FrameRestorationType {
+    /// Remap_Lr_Type in the specification
+    fn from_lr_type(lr_type: u64) -> FrameRestorationType {
+        match lr_type {
+            0 => FrameRestorationType::None,
+            1 => FrameRestorationType::Switchable,
+            2 => FrameRestorationType::Wiener,
+            _ => FrameRestorationType::Sgrproj,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LrParams {
+    pub(crate) frame_restoration_type: [FrameRestorationType; 3],
+    pub(crate) loop_restoration_size: [u64; 3],
+    pub(crate) uses_lr: bool,
+    pub(crate) uses_chroma_lr: bool,
+}
+
+impl LrParams {
+    pub fn new(
+        b: &mut BitStream,
+        sh: &ObuSequenceHeader,
+        all_lossless: bool,
+        allow_intrabc: bool,
+        num_planes: u64,
+    ) -> Result<LrParams> {
+        let mut lp = LrParams::default();
+
+        if all_lossless || allow_intrabc || !sh.enable_restoration {
+            return Ok(lp);
+        }
+
+        for i in 0..num_planes as usize {
+            let lr_type = b.f(2, "lr_type")?;
+            lp.frame_restoration_type[i] = FrameRestorationType::from_lr_type(lr_type);
+
+            if lp.frame_restoration_type[i] != FrameRestorationType::None {
+                lp.uses_lr = true;
+                if i > 0 {
+                    lp.uses_chroma_lr = true;
+                }
+            }
+        }
+
+        if lp.uses_lr {
+            let mut lr_unit_shift = b.f(1, "lr_unit_shift")?;
+            if sh.use_128x128_superblock {
+                lr_unit_shift += 1;
+            } else if lr_unit_shift != 0 {
+                lr_unit_shift += b.f(1, "lr_unit_extra_shift")?;
+            }
+
+            lp.loop_restoration_size[0] = RESTORATION_TILESIZE_MAX >> (2 - lr_unit_shift);
+
+            let cc = &sh.color_config;
+            let lr_uv_shift = match cc.subsampling_x && cc.subsampling_y && lp.uses_chroma_lr {
+                true => b.f(1, "lr_uv_shift")?,
+                false => 0,
+            };
+
+            lp.loop_restoration_size[1] = lp.loop_restoration_size[0] >> lr_uv_shift;
+            lp.loop_restoration_size[2] = lp.loop_restoration_size[0] >> lr_uv_shift;
+        }
+
+        Ok(lp)
+    }
+
+    pub fn frame_restoration_type(&self) -> [FrameRestorationType; 3] {
+        self.frame_restoration_type
+    }
+
+    /// Size of the loop restoration units of each plane in samples
+    pub fn loop_restoration_size(&self) -> [u64; 3] {
+        self.loop_restoration_size
+    }
+
+    pub fn uses_lr(&self) -> bool {
+        self.uses_lr
+    }
+
+    pub fn uses_chroma_lr(&self) -> bool {
+        self.uses_chroma_lr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameRestorationType, LrParams};
+    use crate::{
+        bits::bitstream::BitStream, obu::obu_sequence_header::ObuSequenceHeader,
+        test_util::sequence_header,
+    };
+
+    #[test]
+    fn lr_params() {
+        let sh = ObuSequenceHeader {
+            enable_restoration: true,
+            ..sequence_header(352, 288)
+        };
+
+        // Wiener for Y, none for U, sgrproj for V, lr_unit_shift with extra shift, lr_uv_shift
+        let mut bs = BitStream::new(vec![0b1000_1111, 0b1000_0000]);
+        let lp = LrParams::new(&mut bs, &sh, false, false, 3).unwrap();
+
+        assert_eq!(
+            [
+                FrameRestorationType::Wiener,
+                FrameRestorationType::None,
+                FrameRestorationType::Sgrproj
+            ],
+            lp.frame_restoration_type
+        );
+        assert!(lp.uses_lr);
+        assert!(lp.uses_chroma_lr);
+        assert_eq!([256, 128, 128], lp.loop_restoration_size);
+        assert_eq!(9, bs.get_position());
+    }
+}
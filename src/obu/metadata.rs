@@ -0,0 +1,36 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+/// The metadata payload is kept as raw bytes, including its trailing bits
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    pub(crate) metadata_type: u64,
+    pub(crate) payload: Vec<u8>,
+}
+
+impl Metadata {
+    pub fn new(b: &mut BitStream, sz: u64) -> Result<Metadata> {
+        let metadata_type = b.leb128("metadata_type")?;
+
+        let payload_size = sz
+            .checked_sub(b.leb_128_bytes)
+            .ok_or_else(|| b.invalid_value("obu_size", sz))?;
+
+        let mut payload = Vec::new();
+        for _ in 0..payload_size {
+            payload.push(b.f(8, "metadata_payload")? as u8);
+        }
+
+        Ok(Metadata {
+            metadata_type,
+            payload,
+        })
+    }
+
+    pub fn metadata_type(&self) -> u64 {
+        self.metadata_type
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+}
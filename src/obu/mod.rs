@@ -1,8 +1,25 @@
-mod color_config;
-mod decoder_model_info;
-mod obu_frame_header;
-mod obu_header;
-mod obu_sequence_header;
+pub mod cdef_params;
+pub mod color_config;
+pub mod decoder_model_info;
+pub mod delta_params;
+pub mod film_grain_params;
+pub mod frame_size;
+pub mod global_motion_params;
+pub mod loop_filter_params;
+pub mod lr_params;
+pub mod metadata;
+pub mod obu_frame_header;
+pub mod obu_header;
+pub mod obu_sequence_header;
 pub mod open_bitstream_unit;
-mod operating_parameters_info;
-mod timing_info;
+pub mod operating_parameters_info;
+pub mod operating_point;
+pub mod quantization_params;
+pub mod segmentation_params;
+pub mod set_frame_refs;
+pub mod skip_mode_params;
+pub mod tile_group;
+pub mod tile_info;
+pub mod tile_layout;
+pub mod tile_list;
+pub mod timing_info;
@@ -1,83 +1,181 @@
-use crate::{bits::bitstream::BitStream, State};
+use crate::{
+    bits::bitstream::BitStream,
+    error::{CarpError, ErrorKind, Result},
+    State,
+};
 
 use super::{
+    cdef_params::CdefParams,
+    delta_params::{DeltaLfParams, DeltaQParams},
+    film_grain_params::FilmGrainParams,
+    frame_size::FrameSize,
+    global_motion_params::GlobalMotionParams,
+    loop_filter_params::LoopFilterParams,
+    lr_params::LrParams,
     obu_header::ObuHeader,
     obu_sequence_header::{ObuSequenceHeader, SELECT_INTEGER_MV, SELECT_SCREEN_CONTENT_TOOLS},
+    quantization_params::QuantizationParams,
+    segmentation_params::{SegmentationParams, MAX_SEGMENTS},
+    set_frame_refs::set_frame_refs,
+    skip_mode_params::SkipModeParams,
+    tile_info::TileInfo,
+    tile_layout::TileLayout,
 };
 
 pub struct ObuFrameHeader {
-    uncompressed_header: UncompressedHeader,
+    pub(crate) uncompressed_header: UncompressedHeader,
+    pub(crate) frame_header_copy: bool,
 }
 
 impl ObuFrameHeader {
     pub fn new(
         b: &mut BitStream,
         state: &mut State,
-        sh: ObuSequenceHeader,
-        h: ObuHeader,
-        // Taken from previous uncompressed_header
-        old_frame_id: Option<u64>,
-    ) -> ObuFrameHeader {
+        sh: &ObuSequenceHeader,
+        h: &ObuHeader,
+    ) -> Result<ObuFrameHeader> {
         let uh: UncompressedHeader;
         if state.seen_frame_header {
-            todo!("frame_header_copy()");
+            return Ok(ObuFrameHeader {
+                uncompressed_header: frame_header_copy(b, state)?,
+                frame_header_copy: true,
+            });
         } else {
             state.seen_frame_header = true;
-            uh = UncompressedHeader::new(b, sh, h, state, old_frame_id);
+
+            let start_position = b.get_position();
+            uh = UncompressedHeader::new(b, sh, h, state)?;
 
             if uh.show_existing_frame {
-                todo!("decode_frame_wrapup()");
+                // decode_frame_wrapup() loads a shown key frame and refreshes all references with it
+                if uh.frame_type == KEY_FRAME {
+                    state.order_hint = state.ref_order_hint[uh.frame_to_show_map_idx];
+                    state.current_frame_id = state.ref_frame_id[uh.frame_to_show_map_idx];
+                    uh.reference_frame_update(state);
+                }
                 state.seen_frame_header = false;
             } else {
+                // Tiles aren't decoded, so the references are updated right away instead of
+                // after the last tile group
+                uh.reference_frame_update(state);
+                state.tile_info = uh.tile_info.clone();
+                state.frame_header = Some(uh.clone());
+                state.frame_header_bits = b.copy_bits(start_position, b.get_position());
+                state.frame_header_bit_len = (b.get_position() - start_position) as u64;
                 state.tile_num = false;
                 state.seen_frame_header = true;
             }
         }
 
-        ObuFrameHeader {
+        Ok(ObuFrameHeader {
             uncompressed_header: uh,
+            frame_header_copy: false,
+        })
+    }
+
+    pub fn uncompressed_header(&self) -> &UncompressedHeader {
+        &self.uncompressed_header
+    }
+
+    /// The OBU repeated the frame header of the current frame, which was verified to be
+    /// identical
+    pub fn frame_header_copy(&self) -> bool {
+        self.frame_header_copy
+    }
+}
+
+/// Reads frame_header_copy(), which has to repeat the bits of the first frame header of the
+/// frame exactly
+fn frame_header_copy(b: &mut BitStream, state: &State) -> Result<UncompressedHeader> {
+    let uh = state
+        .frame_header
+        .clone()
+        .ok_or_else(|| b.unsupported("frame_header_copy"))?;
+
+    let mut original = BitStream::new(state.frame_header_bits.clone());
+    let mut remaining = state.frame_header_bit_len;
+
+    while remaining > 0 {
+        let n = remaining.min(32);
+        let position = b.get_position();
+
+        let copy = b.f(n, "frame_header_copy")?;
+        let expected = original.f(n, "frame_header_copy")?;
+
+        if copy != expected {
+            let first_difference = (copy ^ expected).leading_zeros() as usize - (64 - n as usize);
+            return Err(CarpError::new(
+                position + first_difference,
+                "frame_header_copy",
+                ErrorKind::FrameHeaderMismatch,
+            ));
         }
+
+        remaining -= n;
     }
+
+    Ok(uh)
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct UncompressedHeader {
-    show_existing_frame: bool,
-    frame_type: u64,
-    show_frame: bool,
-    showable_frame: bool,
-    frame_to_show_map_idx: usize,
-    refresh_frame_flags: u64,
-    display_frame_id: u64,
-    error_resilient_mode: bool,
-    disable_cdf_update: bool,
-    allow_screen_content_tools: bool,
-    force_integer_mv: bool,
-    current_frame_id: u64,
-    frame_size_override_flag: bool,
-    primary_ref_frame: u64,
-    buffer_removal_time: Vec<u64>,
-    allow_high_precision_mv: bool,
-    use_ref_frame_mvs: bool,
-    allow_intrabc: bool,
-    ref_order_hint: Vec<u64>,
-    frame_refs_short_signaling: bool,
-    last_frame_idx: usize,
-    gold_frame_idx: usize,
-    ref_frame_idx: Vec<usize>,
-    expected_frame_id: Vec<u64>,
-    is_motion_mode_switchable: bool,
-    disable_frame_end_update_cdf: bool,
+    pub(crate) show_existing_frame: bool,
+    pub(crate) frame_type: u64,
+    pub(crate) show_frame: bool,
+    pub(crate) showable_frame: bool,
+    pub(crate) frame_to_show_map_idx: usize,
+    pub(crate) frame_presentation_time: Option<u64>,
+    pub(crate) refresh_frame_flags: u64,
+    pub(crate) display_frame_id: u64,
+    pub(crate) error_resilient_mode: bool,
+    pub(crate) disable_cdf_update: bool,
+    pub(crate) allow_screen_content_tools: bool,
+    pub(crate) force_integer_mv: bool,
+    pub(crate) current_frame_id: u64,
+    pub(crate) frame_size_override_flag: bool,
+    pub(crate) primary_ref_frame: u64,
+    pub(crate) buffer_removal_time: Vec<u64>,
+    pub(crate) allow_high_precision_mv: bool,
+    pub(crate) interpolation_filter: InterpolationFilter,
+    pub(crate) use_ref_frame_mvs: bool,
+    pub(crate) frame_size: FrameSize,
+    pub(crate) allow_intrabc: bool,
+    pub(crate) ref_order_hint: [u64; NUM_REF_FRAMES],
+    pub(crate) frame_refs_short_signaling: bool,
+    pub(crate) last_frame_idx: usize,
+    pub(crate) gold_frame_idx: usize,
+    pub(crate) ref_frame_idx: [usize; REFS_PER_FRAME],
+    pub(crate) expected_frame_id: [u64; REFS_PER_FRAME],
+    pub(crate) is_motion_mode_switchable: bool,
+    pub(crate) disable_frame_end_update_cdf: bool,
+    pub(crate) tile_info: TileInfo,
+    pub(crate) quantization_params: QuantizationParams,
+    pub(crate) segmentation_params: SegmentationParams,
+    pub(crate) delta_q_params: DeltaQParams,
+    pub(crate) delta_lf_params: DeltaLfParams,
+    pub(crate) coded_lossless: bool,
+    pub(crate) all_lossless: bool,
+    pub(crate) lossless_array: [bool; MAX_SEGMENTS],
+    pub(crate) seg_qm_level: [[u64; 3]; MAX_SEGMENTS],
+    pub(crate) loop_filter_params: LoopFilterParams,
+    pub(crate) cdef_params: CdefParams,
+    pub(crate) lr_params: LrParams,
+    pub(crate) tx_mode: TxMode,
+    pub(crate) reference_select: bool,
+    pub(crate) skip_mode_params: SkipModeParams,
+    pub(crate) allow_warped_motion: bool,
+    pub(crate) reduced_tx_set: bool,
+    pub(crate) global_motion_params: GlobalMotionParams,
+    pub(crate) film_grain_params: FilmGrainParams,
 }
 
 impl UncompressedHeader {
     pub fn new(
         b: &mut BitStream,
-        sh: ObuSequenceHeader,
-        header: ObuHeader,
+        sh: &ObuSequenceHeader,
+        header: &ObuHeader,
         state: &mut State,
-        old_frame_id: Option<u64>,
-    ) -> UncompressedHeader {
+    ) -> Result<UncompressedHeader> {
         let mut uh = UncompressedHeader::default();
 
         let id_len = match sh.frame_id_numbers_present_flag {
@@ -96,82 +194,96 @@ impl UncompressedHeader {
             uh.show_frame = true;
             uh.showable_frame = false;
         } else {
-            uh.show_existing_frame = b.f(1) != 0;
+            uh.show_existing_frame = b.f(1, "show_existing_frame")? != 0;
 
             if uh.show_existing_frame {
-                uh.frame_to_show_map_idx = b.f(3) as usize;
+                uh.frame_to_show_map_idx = b.f(3, "frame_to_show_map_idx")? as usize;
 
                 if sh.decoder_model_info_present_flag
-                    && !sh.timing_info.unwrap().equal_picture_interval
+                    && !sh.timing_info.as_ref().unwrap().equal_picture_interval
                 {
-                    todo!("temporal_point_info()");
+                    uh.frame_presentation_time = Some(temporal_point_info(b, sh)?);
                 }
 
                 uh.refresh_frame_flags = 0;
                 if sh.frame_id_numbers_present_flag {
-                    uh.display_frame_id = b.f(id_len);
+                    uh.display_frame_id = b.f(id_len, "display_frame_id")?;
+
+                    let idx = uh.frame_to_show_map_idx;
+                    if !state.ref_valid[idx] || uh.display_frame_id != state.ref_frame_id[idx] {
+                        return Err(b.error("display_frame_id", ErrorKind::FrameLoss));
+                    }
                 }
 
                 uh.frame_type = state.ref_frame_type[uh.frame_to_show_map_idx];
+                uh.frame_size = state.ref_frame_size[uh.frame_to_show_map_idx];
 
                 if uh.frame_type == KEY_FRAME {
                     uh.refresh_frame_flags = all_frames;
+
+                    // load_reference_frame() restores the saved state, so that the refresh
+                    // doesn't overwrite the other slots with defaults
+                    let idx = uh.frame_to_show_map_idx;
+                    uh.segmentation_params = state.ref_segmentation_params[idx].clone();
+                    uh.loop_filter_params = state.ref_loop_filter_params[idx].clone();
+                    uh.global_motion_params = state.ref_global_motion_params[idx].clone();
                 }
 
                 if sh.film_grain_params_present {
-                    todo!("load_grain_params( frame_to_show_map_idx )");
+                    uh.film_grain_params =
+                        state.ref_film_grain_params[uh.frame_to_show_map_idx].clone();
                 }
 
-                return uh;
+                return Ok(uh);
             }
 
-            uh.frame_type = b.f(2);
+            uh.frame_type = b.f(2, "frame_type")?;
 
             state.frame_is_intra = uh.frame_type == INTRA_ONLY_FRAME || uh.frame_type == KEY_FRAME;
-            uh.show_frame = b.f(1) != 0;
+            uh.show_frame = b.f(1, "show_frame")? != 0;
 
             if uh.show_frame
                 && sh.decoder_model_info_present_flag
-                && !sh.timing_info.unwrap().equal_picture_interval
+                && !sh.timing_info.as_ref().unwrap().equal_picture_interval
             {
-                todo!("temporal_point_info()");
+                uh.frame_presentation_time = Some(temporal_point_info(b, sh)?);
             }
 
             if uh.show_frame {
                 uh.showable_frame = uh.frame_type != KEY_FRAME;
             } else {
-                uh.showable_frame = b.f(1) != 0;
+                uh.showable_frame = b.f(1, "showable_frame")? != 0;
             }
 
             if uh.frame_type == SWITCH_FRAME || uh.frame_type == KEY_FRAME && uh.show_frame {
                 uh.error_resilient_mode = true;
             } else {
-                uh.error_resilient_mode = b.f(1) != 0;
+                uh.error_resilient_mode = b.f(1, "error_resilient_mode")? != 0;
             }
         }
 
         if uh.frame_type == KEY_FRAME && uh.show_frame {
             for i in 0..NUM_REF_FRAMES {
-                state.ref_valid.insert(i, false);
-                state.ref_order_hint.insert(i, 0);
+                state.ref_valid[i] = false;
+                state.ref_order_hint[i] = 0;
             }
 
             for i in 0..REFS_PER_FRAME {
-                state.order_hints.insert(LAST_FRAME + i, 0);
+                state.order_hints[LAST_FRAME + i] = 0;
             }
         }
 
-        uh.disable_cdf_update = b.f(1) != 0;
+        uh.disable_cdf_update = b.f(1, "disable_cdf_update")? != 0;
 
         if sh.seq_force_screen_content_tools == SELECT_SCREEN_CONTENT_TOOLS {
-            uh.allow_screen_content_tools = b.f(1) != 0;
+            uh.allow_screen_content_tools = b.f(1, "allow_screen_content_tools")? != 0;
         } else {
-            uh.allow_screen_content_tools = sh.seq_choose_screen_content_tools;
+            uh.allow_screen_content_tools = sh.seq_force_screen_content_tools != 0;
         }
 
         if uh.allow_screen_content_tools {
             if sh.seq_force_integer_mv == SELECT_INTEGER_MV {
-                uh.force_integer_mv = b.f(1) != 0;
+                uh.force_integer_mv = b.f(1, "force_integer_mv")? != 0;
             } else {
                 uh.force_integer_mv = sh.seq_force_integer_mv != 0;
             }
@@ -184,9 +296,19 @@ impl UncompressedHeader {
         }
 
         if sh.frame_id_numbers_present_flag {
-            state.prev_frame_id = old_frame_id.unwrap_or(0);
-            uh.current_frame_id = b.f(id_len);
-            todo!("mark_ref_frames( idLen )");
+            let prev_frame_id = state.current_frame_id;
+            uh.current_frame_id = b.f(id_len, "current_frame_id")?;
+            state.current_frame_id = uh.current_frame_id;
+
+            if uh.frame_type != KEY_FRAME || !uh.show_frame {
+                let diff_frame_id =
+                    (uh.current_frame_id + (1 << id_len) - prev_frame_id) % (1 << id_len);
+                if diff_frame_id == 0 || diff_frame_id >= 1 << (id_len - 1) {
+                    return Err(b.invalid_value("current_frame_id", uh.current_frame_id));
+                }
+            }
+
+            mark_ref_frames(state, sh, id_len);
         } else {
             uh.current_frame_id = 0;
         }
@@ -196,43 +318,38 @@ impl UncompressedHeader {
         } else if sh.reduced_still_picture_header {
             uh.frame_size_override_flag = false;
         } else {
-            uh.frame_size_override_flag = b.f(1) != 0;
+            uh.frame_size_override_flag = b.f(1, "frame_size_override_flag")? != 0;
         }
 
-        state.order_hint = b.f(state.order_hint_bits);
+        state.order_hint = b.f(state.order_hint_bits, "order_hint")?;
 
         if state.frame_is_intra || uh.error_resilient_mode {
             uh.primary_ref_frame = PRIMARY_REF_NONE;
         } else {
-            uh.primary_ref_frame = b.f(3);
+            uh.primary_ref_frame = b.f(3, "primary_ref_frame")?;
         }
 
         if sh.decoder_model_info_present_flag {
-            let buffer_removal_time_present_flag = b.f(1) != 0;
+            let buffer_removal_time_present_flag = b.f(1, "buffer_removal_time_present_flag")? != 0;
             if buffer_removal_time_present_flag {
-                for op_num in 0..=sh.operating_points_cnt_minus_1 {
-                    if sh.decoder_model_present_for_this_op[op_num] {
-                        let op_pt_idc = sh.operating_point_idc[op_num];
-                        let in_temporal_layer = ((op_pt_idc
-                            >> header.obu_extension_header.clone().unwrap().temporal_id)
-                            & 1)
-                            != 0;
-                        let in_spatial_layer = ((op_pt_idc
-                            >> header.obu_extension_header.clone().unwrap().spatial_id + 8)
-                            & 1)
-                            != 0;
-
-                        if op_pt_idc == 0 || (in_temporal_layer && in_spatial_layer) {
-                            uh.buffer_removal_time.insert(
-                                op_num,
-                                b.f(sh
-                                    .decoder_model_info
-                                    .clone()
-                                    .unwrap()
-                                    .buffer_removal_time_length_minus_1
-                                    + 1),
-                            );
-                        }
+                uh.buffer_removal_time = vec![0; sh.operating_points_cnt_minus_1 + 1];
+                let extension_header = header.obu_extension_header.clone().unwrap_or_default();
+
+                for (op_num, op) in sh.operating_points.iter().enumerate() {
+                    if op.decoder_model_present_for_this_op
+                        && op.contains_layer(
+                            extension_header.temporal_id,
+                            extension_header.spatial_id,
+                        )
+                    {
+                        uh.buffer_removal_time[op_num] = b.f(
+                            sh.decoder_model_info
+                                .clone()
+                                .unwrap()
+                                .buffer_removal_time_length_minus_1
+                                + 1,
+                            "buffer_removal_time",
+                        )?;
                     }
                 }
             }
@@ -245,86 +362,96 @@ impl UncompressedHeader {
         if uh.frame_type == SWITCH_FRAME || uh.frame_type == KEY_FRAME && uh.show_frame {
             uh.refresh_frame_flags = all_frames;
         } else {
-            uh.refresh_frame_flags = b.f(8);
+            uh.refresh_frame_flags = b.f(8, "refresh_frame_flags")?;
         }
 
-        if !state.frame_is_intra || uh.refresh_frame_flags != all_frames {
-            if uh.error_resilient_mode && sh.enable_order_hint {
-                for i in 0..NUM_REF_FRAMES {
-                    uh.ref_order_hint[i] = b.f(state.order_hint_bits);
-                    if uh.ref_order_hint[i] != state.ref_order_hint[i] {
-                        state.ref_valid[i] = false;
-                    }
+        if (!state.frame_is_intra || uh.refresh_frame_flags != all_frames)
+            && uh.error_resilient_mode
+            && sh.enable_order_hint
+        {
+            for i in 0..NUM_REF_FRAMES {
+                uh.ref_order_hint[i] = b.f(state.order_hint_bits, "ref_order_hint")?;
+                if uh.ref_order_hint[i] != state.ref_order_hint[i] {
+                    state.ref_valid[i] = false;
                 }
             }
         }
 
         if state.frame_is_intra {
-            todo!("frame_size()");
-            todo!("render_size()");
+            uh.frame_size = FrameSize::new(b, sh, uh.frame_size_override_flag)?;
 
-            if uh.allow_screen_content_tools && state.upscaled_width == state.frame_width {
-                uh.allow_intrabc = b.f(1) != 0;
+            if uh.allow_screen_content_tools
+                && uh.frame_size.upscaled_width == uh.frame_size.frame_width
+            {
+                uh.allow_intrabc = b.f(1, "allow_intrabc")? != 0;
             }
         } else {
             if !sh.enable_order_hint {
                 uh.frame_refs_short_signaling = false;
             } else {
-                uh.frame_refs_short_signaling = b.f(1) != 0;
+                uh.frame_refs_short_signaling = b.f(1, "frame_refs_short_signaling")? != 0;
 
                 if uh.frame_refs_short_signaling {
-                    uh.last_frame_idx = b.f(3) as usize;
-                    uh.gold_frame_idx = b.f(3) as usize;
+                    uh.last_frame_idx = b.f(3, "last_frame_idx")? as usize;
+                    uh.gold_frame_idx = b.f(3, "gold_frame_idx")? as usize;
 
-                    todo!("set_frame_refs()");
+                    uh.ref_frame_idx =
+                        set_frame_refs(b, state, uh.last_frame_idx, uh.gold_frame_idx)?;
                 }
             }
 
             for i in 0..REFS_PER_FRAME {
                 if !uh.frame_refs_short_signaling {
-                    uh.ref_frame_idx[i] = b.f(3) as usize;
+                    uh.ref_frame_idx[i] = b.f(3, "ref_frame_idx")? as usize;
                 }
                 if sh.frame_id_numbers_present_flag {
                     let n = sh.delta_frame_id_length_minus_2 + 2;
-                    state.delta_frame_id = b.f(n) + 1;
-                    uh.expected_frame_id[i] = (uh.current_frame_id + (1 << id_len)
-                        - state.delta_frame_id)
-                        % (1 << id_len);
+                    let delta_frame_id = b.f(n, "delta_frame_id_minus_1")? + 1;
+                    uh.expected_frame_id[i] =
+                        (uh.current_frame_id + (1 << id_len) - delta_frame_id) % (1 << id_len);
+
+                    let idx = uh.ref_frame_idx[i];
+                    if !state.ref_valid[idx] || uh.expected_frame_id[i] != state.ref_frame_id[idx] {
+                        return Err(b.error("delta_frame_id_minus_1", ErrorKind::FrameLoss));
+                    }
                 }
             }
 
             if uh.frame_size_override_flag && !uh.error_resilient_mode {
-                todo!("frame_size_with_refs( )");
+                let ref_frame_sizes = uh
+                    .ref_frame_idx
+                    .map(|idx| state.ref_valid[idx].then_some(state.ref_frame_size[idx]));
+                uh.frame_size =
+                    FrameSize::with_refs(b, sh, uh.frame_size_override_flag, &ref_frame_sizes)?;
             } else {
-                //todo!("frame_size()");
-                //todo!("render_size()");
+                uh.frame_size = FrameSize::new(b, sh, uh.frame_size_override_flag)?;
             }
 
             if uh.force_integer_mv {
                 uh.allow_high_precision_mv = false;
             } else {
-                uh.allow_high_precision_mv = b.f(1) != 0;
+                uh.allow_high_precision_mv = b.f(1, "allow_high_precision_mv")? != 0;
             }
 
-            //todo!("read_interpolation_filter()");
-            uh.is_motion_mode_switchable = b.f(1) != 0;
+            uh.interpolation_filter = InterpolationFilter::new(b)?;
+            uh.is_motion_mode_switchable = b.f(1, "is_motion_mode_switchable")? != 0;
 
             if uh.error_resilient_mode || !sh.enable_ref_frame_mvs {
                 uh.use_ref_frame_mvs = false;
             } else {
-                uh.use_ref_frame_mvs = b.f(1) != 0;
+                uh.use_ref_frame_mvs = b.f(1, "use_ref_frame_mvs")? != 0;
             }
 
             for i in 0..REFS_PER_FRAME {
-                let ref_frame = LAST_FRAME + 1;
+                let ref_frame = LAST_FRAME + i;
                 let hint = state.ref_order_hint[uh.ref_frame_idx[i]];
                 state.order_hints[ref_frame] = hint;
 
                 if !sh.enable_order_hint {
                     state.ref_frame_sign_bias[ref_frame] = 0;
                 } else {
-                    // todo!();
-                    //state.ref_frame_sign_bias[ref_frame] = get_relative_dist(hint, state.order_hint) > 0
+                    state.ref_frame_sign_bias[ref_frame] =
+                        (state.get_relative_dist(hint, state.order_hint) > 0) as u64;
                 }
             }
         }
@@ -332,31 +459,731 @@ impl UncompressedHeader {
         if sh.reduced_still_picture_header || uh.disable_cdf_update {
             uh.disable_frame_end_update_cdf = true;
         } else {
-            uh.disable_frame_end_update_cdf = b.f(1) != 0;
+            uh.disable_frame_end_update_cdf = b.f(1, "disable_frame_end_update_cdf")? != 0;
+        }
+
+        // init_non_coeff_cdfs() and load_cdfs() are not needed as tile data is not decoded,
+        // setup_past_independence() and load_previous() follow from primary_ref_slot below
+
+        uh.tile_info = TileInfo::new(b, sh, &uh.frame_size)?;
+        uh.quantization_params = QuantizationParams::new(b, &sh.color_config, state.num_planes)?;
+
+        // load_previous() takes segmentation features, loop filter deltas and global motion
+        // params from the primary reference frame
+        let primary_ref_slot = match uh.primary_ref_frame {
+            PRIMARY_REF_NONE => None,
+            primary_ref_frame => Some(uh.ref_frame_idx[primary_ref_frame as usize]),
+        };
+
+        let previous_segmentation_params = primary_ref_slot
+            .map(|slot| state.ref_segmentation_params[slot].clone())
+            .unwrap_or_default();
+        uh.segmentation_params =
+            SegmentationParams::new(b, primary_ref_slot.is_none(), &previous_segmentation_params)?;
+
+        uh.delta_q_params = DeltaQParams::new(b, uh.quantization_params.base_q_idx)?;
+        uh.delta_lf_params =
+            DeltaLfParams::new(b, uh.delta_q_params.delta_q_present, uh.allow_intrabc)?;
+
+        let qp = &uh.quantization_params;
+        uh.coded_lossless = true;
+        for segment_id in 0..MAX_SEGMENTS {
+            let qindex = uh.segmentation_params.qindex(qp.base_q_idx, segment_id);
+            uh.lossless_array[segment_id] = qindex == 0 && qp.deltas_are_zero();
+
+            if !uh.lossless_array[segment_id] {
+                uh.coded_lossless = false;
+            }
+
+            if qp.using_qmatrix {
+                uh.seg_qm_level[segment_id] = match uh.lossless_array[segment_id] {
+                    true => [15, 15, 15],
+                    false => [qp.qm_y, qp.qm_u, qp.qm_v],
+                };
+            }
+        }
+        uh.all_lossless =
+            uh.coded_lossless && uh.frame_size.frame_width == uh.frame_size.upscaled_width;
+
+        let previous_loop_filter_params = primary_ref_slot
+            .map(|slot| state.ref_loop_filter_params[slot].clone())
+            .unwrap_or_default();
+        uh.loop_filter_params = LoopFilterParams::new(
+            b,
+            uh.coded_lossless,
+            uh.allow_intrabc,
+            state.num_planes,
+            &previous_loop_filter_params,
+        )?;
+        uh.cdef_params = CdefParams::new(
+            b,
+            sh.enable_cdef,
+            uh.coded_lossless,
+            uh.allow_intrabc,
+            state.num_planes,
+        )?;
+        uh.lr_params = LrParams::new(b, sh, uh.all_lossless, uh.allow_intrabc, state.num_planes)?;
+
+        uh.tx_mode = TxMode::new(b, uh.coded_lossless)?;
+
+        if state.frame_is_intra {
+            uh.reference_select = false;
+        } else {
+            uh.reference_select = b.f(1, "reference_select")? != 0;
         }
 
-        if uh.primary_ref_frame == PRIMARY_REF_NONE {
-            // TODO
-            // init_non_coeff_cdfs();
-            // setup_past_independence( )
+        uh.skip_mode_params =
+            SkipModeParams::new(b, sh, state, uh.reference_select, &uh.ref_frame_idx)?;
+
+        if state.frame_is_intra || uh.error_resilient_mode || !sh.enable_warped_motion {
+            uh.allow_warped_motion = false;
         } else {
-            // TODO
-            // load_cdfs( ref_frame_idx[ primary_ref_frame ] )
-            // load_previous( )
+            uh.allow_warped_motion = b.f(1, "allow_warped_motion")? != 0;
+        }
+
+        uh.reduced_tx_set = b.f(1, "reduced_tx_set")? != 0;
+
+        let previous_global_motion_params = primary_ref_slot
+            .map(|slot| state.ref_global_motion_params[slot].clone())
+            .unwrap_or_default();
+        uh.global_motion_params = GlobalMotionParams::new(
+            b,
+            state.frame_is_intra,
+            uh.allow_high_precision_mv,
+            &previous_global_motion_params,
+        )?;
+
+        uh.film_grain_params = FilmGrainParams::new(
+            b,
+            sh,
+            state,
+            uh.frame_type,
+            uh.show_frame,
+            uh.showable_frame,
+            &uh.ref_frame_idx,
+        )?;
+
+        Ok(uh)
+    }
+
+    /// Position and size of the tiles of the frame
+    pub fn tile_layout(&self, sh: &ObuSequenceHeader) -> TileLayout {
+        TileLayout::new(&self.tile_info, sh.use_128x128_superblock, &self.frame_size)
+    }
+
+    /// Stores the state of the current frame in every slot of `refresh_frame_flags`
+    fn reference_frame_update(&self, state: &mut State) {
+        for i in 0..NUM_REF_FRAMES {
+            if (self.refresh_frame_flags >> i) & 1 == 1 {
+                state.ref_valid[i] = true;
+                state.ref_frame_type[i] = self.frame_type;
+                state.ref_frame_id[i] = state.current_frame_id;
+                state.ref_order_hint[i] = state.order_hint;
+                state.ref_frame_size[i] = self.frame_size;
+                state.ref_segmentation_params[i] = self.segmentation_params.clone();
+                state.ref_loop_filter_params[i] = self.loop_filter_params.clone();
+                state.ref_global_motion_params[i] = self.global_motion_params.clone();
+                state.ref_film_grain_params[i] = self.film_grain_params.clone();
+            }
         }
+    }
+
+    pub fn show_existing_frame(&self) -> bool {
+        self.show_existing_frame
+    }
+
+    pub fn frame_type(&self) -> u64 {
+        self.frame_type
+    }
+
+    pub fn show_frame(&self) -> bool {
+        self.show_frame
+    }
+
+    pub fn showable_frame(&self) -> bool {
+        self.showable_frame
+    }
+
+    pub fn frame_to_show_map_idx(&self) -> usize {
+        self.frame_to_show_map_idx
+    }
+
+    /// Presentation time in display clock ticks, only present with a decoder model
+    /// that doesn't use an equal picture interval
+    pub fn frame_presentation_time(&self) -> Option<u64> {
+        self.frame_presentation_time
+    }
+
+    pub fn refresh_frame_flags(&self) -> u64 {
+        self.refresh_frame_flags
+    }
+
+    pub fn display_frame_id(&self) -> u64 {
+        self.display_frame_id
+    }
+
+    pub fn error_resilient_mode(&self) -> bool {
+        self.error_resilient_mode
+    }
+
+    pub fn disable_cdf_update(&self) -> bool {
+        self.disable_cdf_update
+    }
+
+    pub fn allow_screen_content_tools(&self) -> bool {
+        self.allow_screen_content_tools
+    }
+
+    pub fn force_integer_mv(&self) -> bool {
+        self.force_integer_mv
+    }
+
+    pub fn current_frame_id(&self) -> u64 {
+        self.current_frame_id
+    }
+
+    pub fn frame_size_override_flag(&self) -> bool {
+        self.frame_size_override_flag
+    }
+
+    pub fn primary_ref_frame(&self) -> u64 {
+        self.primary_ref_frame
+    }
+
+    pub fn buffer_removal_time(&self) -> &[u64] {
+        &self.buffer_removal_time
+    }
+
+    pub fn allow_high_precision_mv(&self) -> bool {
+        self.allow_high_precision_mv
+    }
+
+    pub fn interpolation_filter(&self) -> InterpolationFilter {
+        self.interpolation_filter
+    }
+
+    pub fn use_ref_frame_mvs(&self) -> bool {
+        self.use_ref_frame_mvs
+    }
+
+    pub fn frame_size(&self) -> FrameSize {
+        self.frame_size
+    }
+
+    pub fn allow_intrabc(&self) -> bool {
+        self.allow_intrabc
+    }
+
+    pub fn ref_order_hint(&self) -> [u64; NUM_REF_FRAMES] {
+        self.ref_order_hint
+    }
+
+    pub fn frame_refs_short_signaling(&self) -> bool {
+        self.frame_refs_short_signaling
+    }
+
+    pub fn last_frame_idx(&self) -> usize {
+        self.last_frame_idx
+    }
+
+    pub fn gold_frame_idx(&self) -> usize {
+        self.gold_frame_idx
+    }
+
+    pub fn ref_frame_idx(&self) -> [usize; REFS_PER_FRAME] {
+        self.ref_frame_idx
+    }
+
+    pub fn expected_frame_id(&self) -> [u64; REFS_PER_FRAME] {
+        self.expected_frame_id
+    }
 
-        uh
+    pub fn is_motion_mode_switchable(&self) -> bool {
+        self.is_motion_mode_switchable
     }
+
+    pub fn disable_frame_end_update_cdf(&self) -> bool {
+        self.disable_frame_end_update_cdf
+    }
+
+    pub fn tile_info(&self) -> &TileInfo {
+        &self.tile_info
+    }
+
+    pub fn quantization_params(&self) -> &QuantizationParams {
+        &self.quantization_params
+    }
+
+    pub fn segmentation_params(&self) -> &SegmentationParams {
+        &self.segmentation_params
+    }
+
+    pub fn delta_q_params(&self) -> &DeltaQParams {
+        &self.delta_q_params
+    }
+
+    pub fn delta_lf_params(&self) -> &DeltaLfParams {
+        &self.delta_lf_params
+    }
+
+    /// Whether every segment is coded losslessly
+    pub fn coded_lossless(&self) -> bool {
+        self.coded_lossless
+    }
+
+    /// Whether the frame is lossless, including superres upscaling
+    pub fn all_lossless(&self) -> bool {
+        self.all_lossless
+    }
+
+    pub fn lossless_array(&self) -> [bool; MAX_SEGMENTS] {
+        self.lossless_array
+    }
+
+    /// Quantizer matrix level of every segment for the Y, U and V planes
+    pub fn seg_qm_level(&self) -> [[u64; 3]; MAX_SEGMENTS] {
+        self.seg_qm_level
+    }
+
+    pub fn loop_filter_params(&self) -> &LoopFilterParams {
+        &self.loop_filter_params
+    }
+
+    pub fn cdef_params(&self) -> &CdefParams {
+        &self.cdef_params
+    }
+
+    pub fn lr_params(&self) -> &LrParams {
+        &self.lr_params
+    }
+
+    pub fn tx_mode(&self) -> TxMode {
+        self.tx_mode
+    }
+
+    /// Whether blocks can choose between single and compound prediction
+    pub fn reference_select(&self) -> bool {
+        self.reference_select
+    }
+
+    pub fn skip_mode_params(&self) -> &SkipModeParams {
+        &self.skip_mode_params
+    }
+
+    pub fn allow_warped_motion(&self) -> bool {
+        self.allow_warped_motion
+    }
+
+    pub fn reduced_tx_set(&self) -> bool {
+        self.reduced_tx_set
+    }
+
+    pub fn global_motion_params(&self) -> &GlobalMotionParams {
+        &self.global_motion_params
+    }
+
+    pub fn film_grain_params(&self) -> &FilmGrainParams {
+        &self.film_grain_params
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InterpolationFilter {
+    #[default]
+    EightTap,
+    EightTapSmooth,
+    EightTapSharp,
+    Bilinear,
+    /// The filter is chosen per block
+    Switchable,
+}
+
+impl InterpolationFilter {
+    /// Reads read_interpolation_filter()
+    fn new(b: &mut BitStream) -> Result<InterpolationFilter> {
+        let is_filter_switchable = b.f(1, "is_filter_switchable")? != 0;
+        if is_filter_switchable {
+            return Ok(InterpolationFilter::Switchable);
+        }
+
+        match b.f(2, "interpolation_filter")? {
+            0 => Ok(InterpolationFilter::EightTap),
+            1 => Ok(InterpolationFilter::EightTapSmooth),
+            2 => Ok(InterpolationFilter::EightTapSharp),
+            _ => Ok(InterpolationFilter::Bilinear),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TxMode {
+    #[default]
+    Only4x4,
+    /// The largest transform size that fits the block is used
+    Largest,
+    /// The transform size is coded per block
+    Select,
 }
 
-const NUM_REF_FRAMES: usize = 8;
-const KEY_FRAME: u64 = 0;
-const INTER_FRAME: u64 = 1;
-const INTRA_ONLY_FRAME: u64 = 2;
-const SWITCH_FRAME: u64 = 3;
+impl TxMode {
+    /// Reads read_tx_mode()
+    fn new(b: &mut BitStream, coded_lossless: bool) -> Result<TxMode> {
+        if coded_lossless {
+            return Ok(TxMode::Only4x4);
+        }
 
-const REFS_PER_FRAME: usize = 7;
+        match b.f(1, "tx_mode_select")? != 0 {
+            true => Ok(TxMode::Select),
+            false => Ok(TxMode::Largest),
+        }
+    }
+}
 
-const LAST_FRAME: usize = 1;
+/// Invalidates references whose frame id is too far from the current one, they were
+/// refreshed by frames that are missing
+fn mark_ref_frames(state: &mut State, sh: &ObuSequenceHeader, id_len: u64) {
+    let diff_len = sh.delta_frame_id_length_minus_2 + 2;
+    let current_frame_id = state.current_frame_id;
 
-const PRIMARY_REF_NONE: u64 = 7;
+    for i in 0..NUM_REF_FRAMES {
+        let ref_frame_id = state.ref_frame_id[i];
+
+        if current_frame_id > (1 << diff_len) {
+            if ref_frame_id > current_frame_id || ref_frame_id < current_frame_id - (1 << diff_len)
+            {
+                state.ref_valid[i] = false;
+            }
+        } else if ref_frame_id > current_frame_id
+            && ref_frame_id < (1 << id_len) + current_frame_id - (1 << diff_len)
+        {
+            state.ref_valid[i] = false;
+        }
+    }
+}
+
+fn temporal_point_info(b: &mut BitStream, sh: &ObuSequenceHeader) -> Result<u64> {
+    let frame_presentation_time_length_minus_1 = sh
+        .decoder_model_info
+        .as_ref()
+        .unwrap()
+        .frame_presentation_time_length_minus_1;
+
+    b.f(
+        frame_presentation_time_length_minus_1 + 1,
+        "frame_presentation_time",
+    )
+}
+
+pub const NUM_REF_FRAMES: usize = 8;
+pub const KEY_FRAME: u64 = 0;
+pub const INTER_FRAME: u64 = 1;
+pub const INTRA_ONLY_FRAME: u64 = 2;
+pub const SWITCH_FRAME: u64 = 3;
+
+pub const REFS_PER_FRAME: usize = 7;
+pub const TOTAL_REFS_PER_FRAME: usize = 8;
+
+pub const INTRA_FRAME: usize = 0;
+pub const LAST_FRAME: usize = 1;
+pub const LAST2_FRAME: usize = 2;
+pub const LAST3_FRAME: usize = 3;
+pub const GOLDEN_FRAME: usize = 4;
+pub const BWDREF_FRAME: usize = 5;
+pub const ALTREF2_FRAME: usize = 6;
+pub const ALTREF_FRAME: usize = 7;
+
+pub const PRIMARY_REF_NONE: u64 = 7;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        mark_ref_frames, ObuFrameHeader, TxMode, UncompressedHeader, INTER_FRAME, KEY_FRAME,
+        LAST_FRAME,
+    };
+    use crate::{
+        bits::bitstream::BitStream,
+        error::{CarpError, ErrorKind},
+        obu::{
+            decoder_model_info::DecoderModelInfo,
+            obu_header::{ObuHeader, ObuType},
+            obu_sequence_header::{ObuSequenceHeader, SELECT_INTEGER_MV},
+            operating_point::OperatingPoint,
+            segmentation_params::SEG_LVL_REF_FRAME,
+            timing_info::TimingInfo,
+        },
+        test_util::sequence_header,
+        State,
+    };
+
+    fn frame_header_obu_header() -> ObuHeader {
+        ObuHeader {
+            obu_forbidden_bit: false,
+            obu_type: ObuType::ObuFrameHeader,
+            obu_extension_flag: false,
+            obu_has_size_field: true,
+            obu_reserved_1bit: false,
+            obu_extension_header: None,
+        }
+    }
+
+    #[test]
+    fn show_existing_frame_with_presentation_time() {
+        let sh = ObuSequenceHeader {
+            timing_info_present_flag: true,
+            timing_info: Some(TimingInfo {
+                num_units_in_display_tick: 1,
+                time_scale: 30,
+                equal_picture_interval: false,
+                num_ticks_per_picture_minus_1: 0,
+            }),
+            decoder_model_info_present_flag: true,
+            decoder_model_info: Some(DecoderModelInfo {
+                buffer_delay_length_minus_1: 3,
+                num_units_in_decoding_tick: 1,
+                buffer_removal_time_length_minus_1: 4,
+                frame_presentation_time_length_minus_1: 7,
+            }),
+            operating_points: vec![OperatingPoint::default()],
+            ..Default::default()
+        };
+        let mut state = State::default();
+        state.ref_frame_type[5] = INTER_FRAME;
+        state.ref_frame_size[5].frame_width = 352;
+
+        // show_existing_frame, frame_to_show_map_idx 5, frame_presentation_time 42
+        let mut bs = BitStream::new(vec![0b1101_0010, 0b1010_0000]);
+        let uh =
+            UncompressedHeader::new(&mut bs, &sh, &frame_header_obu_header(), &mut state).unwrap();
+
+        assert!(uh.show_existing_frame);
+        assert_eq!(5, uh.frame_to_show_map_idx);
+        assert_eq!(Some(42), uh.frame_presentation_time);
+        assert_eq!(INTER_FRAME, uh.frame_type);
+        assert_eq!(352, uh.frame_size.frame_width);
+        assert_eq!(12, bs.get_position());
+    }
+
+    fn frame_id_sequence_header() -> ObuSequenceHeader {
+        // 8 bit frame ids, 4 bit deltas
+        ObuSequenceHeader {
+            frame_id_numbers_present_flag: true,
+            delta_frame_id_length_minus_2: 2,
+            additional_frame_id_length_minus_1: 3,
+            operating_points: vec![OperatingPoint::default()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn display_frame_id() {
+        let sh = frame_id_sequence_header();
+        let mut state = State::default();
+        state.ref_valid[2] = true;
+        state.ref_frame_id[2] = 0x17;
+
+        // show_existing_frame, frame_to_show_map_idx 2, display_frame_id 0x17
+        let data = vec![0b1010_0001, 0b0111_0000];
+        let uh = UncompressedHeader::new(
+            &mut BitStream::new(data.clone()),
+            &sh,
+            &frame_header_obu_header(),
+            &mut state,
+        )
+        .unwrap();
+        assert_eq!(0x17, uh.display_frame_id);
+
+        // The frame in slot 2 was replaced by a frame that got lost
+        state.ref_frame_id[2] = 0x16;
+        assert_eq!(
+            Some(CarpError::new(12, "display_frame_id", ErrorKind::FrameLoss)),
+            UncompressedHeader::new(
+                &mut BitStream::new(data),
+                &sh,
+                &frame_header_obu_header(),
+                &mut state
+            )
+            .err()
+        );
+    }
+
+    /// Shown key frame with a single tile, base_q_idx 100, loop filter levels 10, 0, 5
+    /// and 5, tx_mode_select
+    const KEY_FRAME_HEADER: [u8; 7] = [
+        0b0001_0000,
+        0b1000_1100,
+        0b1000_0000,
+        0b0001_0100,
+        0b0000_0000,
+        0b1010_0010,
+        0b1000_0100,
+    ];
+
+    #[test]
+    fn key_frame() {
+        let sh = sequence_header(352, 288);
+        let mut state = State {
+            num_planes: 3,
+            ..Default::default()
+        };
+
+        let mut bs = BitStream::new(KEY_FRAME_HEADER.to_vec());
+        let uh =
+            UncompressedHeader::new(&mut bs, &sh, &frame_header_obu_header(), &mut state).unwrap();
+
+        assert_eq!(KEY_FRAME, uh.frame_type);
+        assert_eq!(0xff, uh.refresh_frame_flags);
+        assert_eq!(
+            (352, 288),
+            (uh.frame_size.frame_width, uh.frame_size.frame_height)
+        );
+        assert_eq!(1, uh.tile_info.tile_cols * uh.tile_info.tile_rows);
+        assert_eq!(100, uh.quantization_params.base_q_idx);
+        assert!(!uh.coded_lossless);
+        assert_eq!([10, 0, 5, 5], uh.loop_filter_params.loop_filter_level);
+        assert_eq!(TxMode::Select, uh.tx_mode);
+        assert!(!uh.reference_select);
+        assert!(!uh.reduced_tx_set);
+        assert_eq!(55, bs.get_position());
+    }
+
+    #[test]
+    fn forced_screen_content_tools() {
+        let sh = ObuSequenceHeader {
+            seq_force_screen_content_tools: 1,
+            seq_force_integer_mv: SELECT_INTEGER_MV,
+            ..sequence_header(352, 288)
+        };
+        let mut state = State {
+            num_planes: 3,
+            ..Default::default()
+        };
+
+        // allow_screen_content_tools is forced on by the sequence header, so the key frame
+        // header carries an allow_intrabc bit after the frame size
+        let mut bs = BitStream::new(vec![
+            0b0001_0100,
+            0b0010_0011,
+            0b0010_0000,
+            0b0000_0101,
+            0b0000_0000,
+            0b0010_1000,
+            0b1010_0001,
+            0b0000_0000,
+        ]);
+        let uh =
+            UncompressedHeader::new(&mut bs, &sh, &frame_header_obu_header(), &mut state).unwrap();
+
+        assert!(uh.allow_screen_content_tools);
+        assert!(!uh.allow_intrabc);
+        assert_eq!(100, uh.quantization_params.base_q_idx);
+        assert_eq!([10, 0, 5, 5], uh.loop_filter_params.loop_filter_level);
+        assert_eq!(TxMode::Select, uh.tx_mode);
+        assert_eq!(57, bs.get_position());
+    }
+
+    #[test]
+    fn show_existing_key_frame_keeps_reference_state() {
+        let sh = sequence_header(352, 288);
+        let mut state = State {
+            num_planes: 3,
+            ..Default::default()
+        };
+        state.ref_frame_type[5] = KEY_FRAME;
+        state.ref_loop_filter_params[5].loop_filter_ref_deltas[LAST_FRAME] = 3;
+        state.ref_loop_filter_params[5].loop_filter_mode_deltas = [2, 0];
+        state.ref_segmentation_params[5].feature_enabled[2][SEG_LVL_REF_FRAME] = true;
+        state.ref_segmentation_params[5].feature_data[2][SEG_LVL_REF_FRAME] = 4;
+
+        // show_existing_frame, frame_to_show_map_idx 5
+        let mut bs = BitStream::new(vec![0b1101_0000]);
+        ObuFrameHeader::new(&mut bs, &mut state, &sh, &frame_header_obu_header()).unwrap();
+
+        // Inter frame with all references in slot 0 as primary reference frame, segmentation
+        // and loop filter deltas enabled without updating them
+        let mut bs = BitStream::new(vec![
+            0b0011_0000,
+            0b0000_0000,
+            0b0000_0000,
+            0b0000_0000,
+            0b0000_0000,
+            0b0100_1000,
+            0b1100_1000,
+            0b0001_0000,
+            0b0000_0000,
+            0b0000_0010,
+            0b1000_0000,
+            0b0000_0000,
+        ]);
+        let uh = ObuFrameHeader::new(&mut bs, &mut state, &sh, &frame_header_obu_header())
+            .unwrap()
+            .uncompressed_header;
+
+        assert_eq!(INTER_FRAME, uh.frame_type);
+        assert_eq!(0, uh.primary_ref_frame);
+        assert_eq!(3, uh.loop_filter_params.loop_filter_ref_deltas[LAST_FRAME]);
+        assert_eq!([2, 0], uh.loop_filter_params.loop_filter_mode_deltas);
+        assert!(uh.segmentation_params.feature_active(2, SEG_LVL_REF_FRAME));
+        assert_eq!(4, uh.segmentation_params.feature_data[2][SEG_LVL_REF_FRAME]);
+        assert_eq!(90, bs.get_position());
+    }
+
+    #[test]
+    fn frame_header_copy() {
+        let sh = sequence_header(352, 288);
+        let mut state = State {
+            num_planes: 3,
+            ..Default::default()
+        };
+
+        let mut bs = BitStream::new(KEY_FRAME_HEADER.to_vec());
+        let fh = ObuFrameHeader::new(&mut bs, &mut state, &sh, &frame_header_obu_header()).unwrap();
+        assert!(!fh.frame_header_copy);
+        assert!(state.seen_frame_header);
+
+        let mut bs = BitStream::new(KEY_FRAME_HEADER.to_vec());
+        let copy =
+            ObuFrameHeader::new(&mut bs, &mut state, &sh, &frame_header_obu_header()).unwrap();
+        assert!(copy.frame_header_copy);
+        assert_eq!(100, copy.uncompressed_header.quantization_params.base_q_idx);
+        assert_eq!(55, bs.get_position());
+
+        // base_q_idx 101 in the copy
+        let mut data = KEY_FRAME_HEADER.to_vec();
+        data[2] = 0b1010_0000;
+        let mut bs = BitStream::new(data);
+        assert_eq!(
+            Some(CarpError::new(
+                18,
+                "frame_header_copy",
+                ErrorKind::FrameHeaderMismatch
+            )),
+            ObuFrameHeader::new(&mut bs, &mut state, &sh, &frame_header_obu_header()).err()
+        );
+    }
+
+    #[test]
+    fn mark_ref_frames_invalidates_old_frames() {
+        let sh = frame_id_sequence_header();
+        let mut state = State {
+            ref_valid: [true; 8],
+            ref_frame_id: [20, 19, 10, 4, 3, 30, 250, 0],
+            current_frame_id: 20,
+            ..Default::default()
+        };
+
+        mark_ref_frames(&mut state, &sh, 8);
+        assert_eq!(
+            [true, true, true, true, false, false, false, false],
+            state.ref_valid
+        );
+
+        // Frame ids wrap around after 255
+        state.ref_valid = [true; 8];
+        state.current_frame_id = 5;
+        mark_ref_frames(&mut state, &sh, 8);
+        assert_eq!(
+            [false, false, false, true, true, false, true, true],
+            state.ref_valid
+        );
+    }
+}
@@ -1,39 +1,68 @@
-use crate::bits::bitstream::BitStream;
+use crate::{bits::bitstream::BitStream, error::Result};
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct ObuHeader {
-    pub obu_forbidden_bit: bool,
-    pub obu_type: ObuType,
-    pub obu_extension_flag: bool,
-    pub obu_has_size_field: bool,
-    pub obu_reserved_1bit: bool,
-    pub obu_extension_header: Option<ObuExtensionHeader>,
+    pub(crate) obu_forbidden_bit: bool,
+    pub(crate) obu_type: ObuType,
+    pub(crate) obu_extension_flag: bool,
+    pub(crate) obu_has_size_field: bool,
+    pub(crate) obu_reserved_1bit: bool,
+    pub(crate) obu_extension_header: Option<ObuExtensionHeader>,
 }
 
 impl ObuHeader {
-    pub fn new(bitstream: &mut BitStream) -> ObuHeader {
-        let obu_forbidden_bit = bitstream.f(1) != 0;
-        let obu_type = ObuType::new(bitstream.f(4));
-        let obu_extension_flag = bitstream.f(1) != 0;
-        let obu_has_size_field = bitstream.f(1) != 0;
-        let obu_reserved_1bit = bitstream.f(1) != 0;
+    pub fn new(bitstream: &mut BitStream) -> Result<ObuHeader> {
+        let obu_forbidden_bit = bitstream.f(1, "obu_forbidden_bit")? != 0;
+        if obu_forbidden_bit {
+            return Err(bitstream.invalid_value("obu_forbidden_bit", 1));
+        }
+
+        let obu_type = ObuType::new(bitstream.f(4, "obu_type")?);
+        let obu_extension_flag = bitstream.f(1, "obu_extension_flag")? != 0;
+        let obu_has_size_field = bitstream.f(1, "obu_has_size_field")? != 0;
+        let obu_reserved_1bit = bitstream.f(1, "obu_reserved_1bit")? != 0;
 
         let obu_extension_header = match obu_extension_flag {
-            true => Some(ObuExtensionHeader::new(bitstream)),
+            true => Some(ObuExtensionHeader::new(bitstream)?),
             false => None,
         };
 
-        ObuHeader {
+        Ok(ObuHeader {
             obu_forbidden_bit,
             obu_type,
             obu_extension_flag,
             obu_has_size_field,
             obu_reserved_1bit,
             obu_extension_header,
-        }
+        })
+    }
+
+    pub fn obu_forbidden_bit(&self) -> bool {
+        self.obu_forbidden_bit
+    }
+
+    pub fn obu_type(&self) -> ObuType {
+        self.obu_type
+    }
+
+    pub fn obu_extension_flag(&self) -> bool {
+        self.obu_extension_flag
+    }
+
+    pub fn obu_has_size_field(&self) -> bool {
+        self.obu_has_size_field
+    }
+
+    pub fn obu_reserved_1bit(&self) -> bool {
+        self.obu_reserved_1bit
+    }
+
+    pub fn obu_extension_header(&self) -> Option<&ObuExtensionHeader> {
+        self.obu_extension_header.as_ref()
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ObuType {
     Reserved,
     ObuSequenceHeader,
@@ -66,24 +95,32 @@ impl ObuType {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ObuExtensionHeader {
-    pub temporal_id: u64,
-    pub spatial_id: u64,
+    pub(crate) temporal_id: u64,
+    pub(crate) spatial_id: u64,
     extension_header_reserved_3bits: u64,
 }
 
 impl ObuExtensionHeader {
-    pub fn new(bitstream: &mut BitStream) -> ObuExtensionHeader {
-        let temporal_id = bitstream.f(3);
-        let spatial_id = bitstream.f(2);
-        let extension_header_reserved_3bits = bitstream.f(3);
+    pub fn new(bitstream: &mut BitStream) -> Result<ObuExtensionHeader> {
+        let temporal_id = bitstream.f(3, "temporal_id")?;
+        let spatial_id = bitstream.f(2, "spatial_id")?;
+        let extension_header_reserved_3bits = bitstream.f(3, "extension_header_reserved_3bits")?;
 
-        ObuExtensionHeader {
+        Ok(ObuExtensionHeader {
             temporal_id,
             spatial_id,
             extension_header_reserved_3bits,
-        }
+        })
+    }
+
+    pub fn temporal_id(&self) -> u64 {
+        self.temporal_id
+    }
+
+    pub fn spatial_id(&self) -> u64 {
+        self.spatial_id
     }
 }
 
@@ -91,6 +128,7 @@ impl ObuExtensionHeader {
 mod tests {
     use crate::{
         bits::bitstream::BitStream,
+        error::ErrorKind,
         obu::obu_header::{ObuExtensionHeader, ObuType},
     };
 
@@ -99,23 +137,33 @@ mod tests {
     #[test]
     fn obu_header() {
         let mut bs = BitStream::new(vec![8, 15]);
-        let obu_header = ObuHeader::new(&mut bs);
+        let obu_header = ObuHeader::new(&mut bs).unwrap();
 
-        assert_eq!(false, obu_header.obu_forbidden_bit);
+        assert!(!obu_header.obu_forbidden_bit);
         assert_eq!(ObuType::ObuSequenceHeader, obu_header.obu_type);
-        assert_eq!(false, obu_header.obu_extension_flag);
-        assert_eq!(false, obu_header.obu_has_size_field);
-        assert_eq!(false, obu_header.obu_reserved_1bit);
+        assert!(!obu_header.obu_extension_flag);
+        assert!(!obu_header.obu_has_size_field);
+        assert!(!obu_header.obu_reserved_1bit);
         assert_eq!(None, obu_header.obu_extension_header);
     }
 
     #[test]
     fn obu_extension_header() {
         let mut bs = BitStream::new(vec![22]);
-        let obu_extension_header = ObuExtensionHeader::new(&mut bs);
+        let obu_extension_header = ObuExtensionHeader::new(&mut bs).unwrap();
 
         assert_eq!(0, obu_extension_header.temporal_id);
         assert_eq!(2, obu_extension_header.spatial_id);
         assert_eq!(6, obu_extension_header.extension_header_reserved_3bits);
     }
+
+    #[test]
+    fn obu_header_truncated() {
+        let mut bs = BitStream::new(vec![12]);
+        let err = ObuHeader::new(&mut bs).err().unwrap();
+
+        assert_eq!(8, err.position);
+        assert_eq!("temporal_id", err.element);
+        assert_eq!(ErrorKind::EndOfData, err.kind);
+    }
 }
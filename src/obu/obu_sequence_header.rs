@@ -1,211 +1,516 @@
-use crate::{bits::bitstream::BitStream, State};
+use crate::{bits::bitstream::BitStream, error::Result, State};
 
 use super::{
     color_config::ColorConfig, decoder_model_info::DecoderModelInfo,
-    operating_parameters_info::OperatingParamtersInfo, timing_info::TimingInfo,
+    operating_point::OperatingPoint, timing_info::TimingInfo,
 };
 
-#[derive(Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ObuSequenceHeader {
-    pub timing_info_present_flag: bool,
-    pub decoder_model_info_present_flag: bool,
-    pub reduced_still_picture_header: bool,
-    pub initial_display_delay_present_flag: bool,
-    pub initial_display_delay_minus_1: Vec<u64>,
-    pub operating_points_cnt_minus_1: usize,
-    pub operating_point_idc: Vec<u64>,
-    pub seq_level_idx: Vec<u64>,
-    pub seq_tier: Vec<u64>,
-    pub decoder_model_present_for_this_op: Vec<bool>,
-    pub initial_display_delay_present_for_this_op: Vec<bool>,
-    pub still_picture: bool,
-
-    pub timing_info: Option<TimingInfo>,
-    pub decoder_model_info: Option<DecoderModelInfo>,
-    pub operating_parameters_info: Option<OperatingParamtersInfo>,
-
-    pub frame_width_bits_minus_1: u64,
-    pub frame_height_bits_minus_1: u64,
-    pub max_frame_width_minus_1: u64,
-    pub max_frame_height_minus_1: u64,
-    pub frame_id_numbers_present_flag: bool,
-    pub delta_frame_id_length_minus_2: u64,
-    pub additional_frame_id_length_minus_1: u64,
-
-    pub use_128x128_superblock: bool,
-    pub enable_filter_intra: bool,
-    pub enable_intra_edge_filter: bool,
-
-    pub enable_interintra_compound: bool,
-    pub enable_masked_compound: bool,
-    pub enable_warped_motion: bool,
-    pub enable_dual_filter: bool,
-    pub enable_order_hint: bool,
-    pub enable_jnt_comp: bool,
-    pub enable_ref_frame_mvs: bool,
-    pub seq_force_screen_content_tools: u64,
-    pub seq_force_integer_mv: u64,
-    pub seq_choose_screen_content_tools: bool,
-    pub seq_choose_integer_mv: bool,
-
-    pub enable_superres: bool,
-    pub enable_cdef: bool,
-    pub enable_restoration: bool,
-    pub film_grain_params_present: bool,
-
-    pub color_config: ColorConfig,
+    pub(crate) seq_profile: u64,
+    pub(crate) timing_info_present_flag: bool,
+    pub(crate) decoder_model_info_present_flag: bool,
+    pub(crate) reduced_still_picture_header: bool,
+    pub(crate) initial_display_delay_present_flag: bool,
+    pub(crate) operating_points_cnt_minus_1: usize,
+    pub(crate) operating_points: Vec<OperatingPoint>,
+    pub(crate) still_picture: bool,
+
+    pub(crate) timing_info: Option<TimingInfo>,
+    pub(crate) decoder_model_info: Option<DecoderModelInfo>,
+
+    pub(crate) frame_width_bits_minus_1: u64,
+    pub(crate) frame_height_bits_minus_1: u64,
+    pub(crate) max_frame_width_minus_1: u64,
+    pub(crate) max_frame_height_minus_1: u64,
+    pub(crate) frame_id_numbers_present_flag: bool,
+    pub(crate) delta_frame_id_length_minus_2: u64,
+    pub(crate) additional_frame_id_length_minus_1: u64,
+
+    pub(crate) use_128x128_superblock: bool,
+    pub(crate) enable_filter_intra: bool,
+    pub(crate) enable_intra_edge_filter: bool,
+
+    pub(crate) enable_interintra_compound: bool,
+    pub(crate) enable_masked_compound: bool,
+    pub(crate) enable_warped_motion: bool,
+    pub(crate) enable_dual_filter: bool,
+    pub(crate) enable_order_hint: bool,
+    pub(crate) enable_jnt_comp: bool,
+    pub(crate) enable_ref_frame_mvs: bool,
+    pub(crate) seq_force_screen_content_tools: u64,
+    pub(crate) seq_force_integer_mv: u64,
+    pub(crate) seq_choose_screen_content_tools: bool,
+    pub(crate) seq_choose_integer_mv: bool,
+
+    pub(crate) enable_superres: bool,
+    pub(crate) enable_cdef: bool,
+    pub(crate) enable_restoration: bool,
+    pub(crate) film_grain_params_present: bool,
+
+    pub(crate) color_config: ColorConfig,
 }
 
 impl ObuSequenceHeader {
-    pub fn new(b: &mut BitStream, state: &mut State) -> ObuSequenceHeader {
-        let mut osh = ObuSequenceHeader::default();
-        let seq_profile = b.f(3);
-        osh.still_picture = b.f(1) != 0;
+    pub fn new(b: &mut BitStream, state: &mut State) -> Result<ObuSequenceHeader> {
+        let seq_profile = b.f(3, "seq_profile")?;
+        if seq_profile > 2 {
+            return Err(b.invalid_value("seq_profile", seq_profile));
+        }
+
+        let mut osh = ObuSequenceHeader {
+            seq_profile,
+            ..Default::default()
+        };
 
-        osh.reduced_still_picture_header = b.f(1) != 0;
+        osh.still_picture = b.f(1, "still_picture")? != 0;
+
+        osh.reduced_still_picture_header = b.f(1, "reduced_still_picture_header")? != 0;
         if osh.reduced_still_picture_header {
-            osh.operating_point_idc.push(0);
-            osh.seq_level_idx.push(0);
-            osh.seq_tier.push(0);
-            osh.decoder_model_present_for_this_op.push(false);
-            osh.initial_display_delay_present_for_this_op.push(false);
+            osh.operating_points.push(OperatingPoint::default());
         } else {
-            osh.timing_info_present_flag = b.f(1) != 0;
+            osh.timing_info_present_flag = b.f(1, "timing_info_present_flag")? != 0;
 
             if osh.timing_info_present_flag {
-                osh.timing_info = Some(TimingInfo::new(b));
-                osh.decoder_model_info_present_flag = b.f(1) != 0;
+                osh.timing_info = Some(TimingInfo::new(b)?);
+                osh.decoder_model_info_present_flag =
+                    b.f(1, "decoder_model_info_present_flag")? != 0;
 
                 if osh.decoder_model_info_present_flag {
-                    osh.decoder_model_info = Some(DecoderModelInfo::new(b));
+                    osh.decoder_model_info = Some(DecoderModelInfo::new(b)?);
                 }
             } else {
                 osh.decoder_model_info_present_flag = false;
             }
 
-            osh.initial_display_delay_present_flag = b.f(1) != 0;
-            osh.operating_points_cnt_minus_1 = b.f(5) as usize;
-
-            // these insertions are wrong if values are supposed to be overwritten
-            for i in 0..=osh.operating_points_cnt_minus_1 {
-                osh.operating_point_idc.insert(i, b.f(12));
-                osh.seq_level_idx.insert(i, b.f(5));
-
-                if *osh.seq_level_idx.get(i).unwrap() > 7 {
-                    osh.seq_tier.insert(i, b.f(1));
-                }
-
-                if osh.decoder_model_info_present_flag {
-                    osh.decoder_model_present_for_this_op.insert(i, b.f(1) != 0);
-
-                    if *osh.decoder_model_present_for_this_op.get(1).unwrap() {
-                        osh.operating_parameters_info = Some(OperatingParamtersInfo::new(
-                            b,
-                            i,
-                            osh.decoder_model_info
-                                .clone()
-                                .unwrap()
-                                .buffer_delay_length_minus_1,
-                        ));
-                    }
-                } else {
-                    osh.decoder_model_present_for_this_op.insert(i, false);
-                }
+            osh.initial_display_delay_present_flag =
+                b.f(1, "initial_display_delay_present_flag")? != 0;
+            osh.operating_points_cnt_minus_1 = b.f(5, "operating_points_cnt_minus_1")? as usize;
 
-                if osh.initial_display_delay_present_flag {
-                    osh.initial_display_delay_present_for_this_op
-                        .insert(i, b.f(1) != 0);
-                    if *osh
-                        .initial_display_delay_present_for_this_op
-                        .get(i)
-                        .unwrap()
-                    {
-                        osh.initial_display_delay_minus_1.insert(i, b.f(4));
-                    }
-                }
+            for _ in 0..=osh.operating_points_cnt_minus_1 {
+                osh.operating_points.push(OperatingPoint::new(
+                    b,
+                    osh.decoder_model_info.as_ref(),
+                    osh.initial_display_delay_present_flag,
+                )?);
             }
         }
 
-        let operating_point = ObuSequenceHeader::choose_operating_point();
+        state.operating_point = osh.choose_operating_point(state.operating_point_selection);
+        state.operating_point_idc = osh.operating_points[state.operating_point].operating_point_idc;
 
-        state.operating_point_idc = *osh.operating_point_idc.get(operating_point).unwrap();
+        osh.frame_width_bits_minus_1 = b.f(4, "frame_width_bits_minus_1")?;
+        osh.frame_height_bits_minus_1 = b.f(4, "frame_height_bits_minus_1")?;
 
-        osh.frame_width_bits_minus_1 = b.f(4);
-        osh.frame_height_bits_minus_1 = b.f(4);
-
-        osh.max_frame_width_minus_1 = b.f(osh.frame_width_bits_minus_1 + 1);
-        osh.max_frame_height_minus_1 = b.f(osh.frame_height_bits_minus_1 + 1);
+        osh.max_frame_width_minus_1 =
+            b.f(osh.frame_width_bits_minus_1 + 1, "max_frame_width_minus_1")?;
+        osh.max_frame_height_minus_1 = b.f(
+            osh.frame_height_bits_minus_1 + 1,
+            "max_frame_height_minus_1",
+        )?;
 
         if osh.reduced_still_picture_header {
             osh.frame_id_numbers_present_flag = false;
         } else {
-            osh.frame_id_numbers_present_flag = b.f(1) != 0;
+            osh.frame_id_numbers_present_flag = b.f(1, "frame_id_numbers_present_flag")? != 0;
         }
 
         if osh.frame_id_numbers_present_flag {
-            osh.delta_frame_id_length_minus_2 = b.f(4);
-            osh.additional_frame_id_length_minus_1 = b.f(3);
+            osh.delta_frame_id_length_minus_2 = b.f(4, "delta_frame_id_length_minus_2")?;
+            osh.additional_frame_id_length_minus_1 =
+                b.f(3, "additional_frame_id_length_minus_1")?;
         }
 
-        osh.use_128x128_superblock = b.f(1) != 0;
-        osh.enable_filter_intra = b.f(1) != 0;
-        osh.enable_intra_edge_filter = b.f(1) != 0;
+        osh.use_128x128_superblock = b.f(1, "use_128x128_superblock")? != 0;
+        osh.enable_filter_intra = b.f(1, "enable_filter_intra")? != 0;
+        osh.enable_intra_edge_filter = b.f(1, "enable_intra_edge_filter")? != 0;
 
         if osh.reduced_still_picture_header {
             osh.seq_force_screen_content_tools = SELECT_SCREEN_CONTENT_TOOLS;
             osh.seq_force_integer_mv = SELECT_INTEGER_MV;
             state.order_hint_bits = 0;
         } else {
-            osh.enable_interintra_compound = b.f(1) != 0;
-            osh.enable_masked_compound = b.f(1) != 0;
-            osh.enable_warped_motion = b.f(1) != 0;
-            osh.enable_dual_filter = b.f(1) != 0;
-            osh.enable_order_hint = b.f(1) != 0;
+            osh.enable_interintra_compound = b.f(1, "enable_interintra_compound")? != 0;
+            osh.enable_masked_compound = b.f(1, "enable_masked_compound")? != 0;
+            osh.enable_warped_motion = b.f(1, "enable_warped_motion")? != 0;
+            osh.enable_dual_filter = b.f(1, "enable_dual_filter")? != 0;
+            osh.enable_order_hint = b.f(1, "enable_order_hint")? != 0;
 
             if osh.enable_order_hint {
-                osh.enable_jnt_comp = b.f(1) != 0;
-                osh.enable_ref_frame_mvs = b.f(1) != 0;
+                osh.enable_jnt_comp = b.f(1, "enable_jnt_comp")? != 0;
+                osh.enable_ref_frame_mvs = b.f(1, "enable_ref_frame_mvs")? != 0;
             }
-            osh.seq_choose_screen_content_tools = b.f(1) != 0;
+            osh.seq_choose_screen_content_tools = b.f(1, "seq_choose_screen_content_tools")? != 0;
             if osh.seq_choose_screen_content_tools {
                 osh.seq_force_screen_content_tools = SELECT_SCREEN_CONTENT_TOOLS;
             } else {
-                osh.seq_force_screen_content_tools = b.f(1);
+                osh.seq_force_screen_content_tools = b.f(1, "seq_force_screen_content_tools")?;
             }
 
             if osh.seq_force_screen_content_tools > 0 {
-                osh.seq_choose_integer_mv = b.f(1) != 0;
+                osh.seq_choose_integer_mv = b.f(1, "seq_choose_integer_mv")? != 0;
 
                 if osh.seq_choose_integer_mv {
                     osh.seq_force_integer_mv = SELECT_INTEGER_MV;
                 } else {
-                    osh.seq_force_integer_mv = b.f(1);
+                    osh.seq_force_integer_mv = b.f(1, "seq_force_integer_mv")?;
                 }
             }
 
             if osh.enable_order_hint {
-                let order_hint_bits_minus_1 = b.f(3);
+                let order_hint_bits_minus_1 = b.f(3, "order_hint_bits_minus_1")?;
                 state.order_hint_bits = order_hint_bits_minus_1 + 1;
             } else {
                 state.order_hint_bits = 0;
             }
         }
 
-        osh.enable_superres = b.f(1) != 0;
-        osh.enable_cdef = b.f(1) != 0;
-        osh.enable_restoration = b.f(1) != 0;
-        osh.film_grain_params_present = b.f(1) != 0;
+        osh.enable_superres = b.f(1, "enable_superres")? != 0;
+        osh.enable_cdef = b.f(1, "enable_cdef")? != 0;
+        osh.enable_restoration = b.f(1, "enable_restoration")? != 0;
+        osh.film_grain_params_present = b.f(1, "film_grain_params_present")? != 0;
+
+        osh.color_config = ColorConfig::new(
+            b,
+            osh.seq_profile,
+            &mut state.bit_depth,
+            &mut state.num_planes,
+        )?;
+
+        Ok(osh)
+    }
+
+    /// The operating point with index `op`, operating point 0 is always present
+    pub fn operating_point(&self, op: usize) -> Option<&OperatingPoint> {
+        self.operating_points.get(op)
+    }
+
+    /// Picks an operating point according to `selection`, falls back to operating point 0
+    /// if no operating point matches.
+    pub fn choose_operating_point(&self, selection: OperatingPointSelection) -> usize {
+        let operating_points = 0..=self.operating_points_cnt_minus_1;
+
+        let operating_point = match selection {
+            OperatingPointSelection::Index(i) => operating_points.contains(&i).then_some(i),
+            OperatingPointSelection::HighestQuality => {
+                operating_points.max_by_key(|op| (self.layer_count(*op), std::cmp::Reverse(*op)))
+            }
+            OperatingPointSelection::MaxLevel(max_level) => {
+                let lowest_level = |op: &usize| (self.operating_points[*op].seq_level_idx, *op);
+                let fitting = operating_points
+                    .clone()
+                    .filter(|op| self.operating_points[*op].seq_level_idx <= max_level)
+                    .min_by_key(lowest_level);
+
+                fitting.or_else(|| operating_points.min_by_key(lowest_level))
+            }
+            OperatingPointSelection::LayerMask(mask) => operating_points
+                .filter(|op| {
+                    let idc = self.operating_points[*op].operating_point_idc;
+                    idc != 0 && idc & !mask == 0
+                })
+                .max_by_key(|op| (self.layer_count(*op), std::cmp::Reverse(*op))),
+        };
+
+        operating_point.unwrap_or(0)
+    }
+
+    /// Number of temporal and spatial layer combinations that are decoded for an operating point,
+    /// an `operating_point_idc` of 0 means that all layers are decoded
+    fn layer_count(&self, op: usize) -> u32 {
+        let idc = self.operating_points[op].operating_point_idc;
+        match idc {
+            0 => u32::MAX,
+            _ => (idc & 0xff).count_ones() * (idc >> 8).count_ones(),
+        }
+    }
+
+    pub fn seq_profile(&self) -> u64 {
+        self.seq_profile
+    }
+
+    pub fn timing_info_present_flag(&self) -> bool {
+        self.timing_info_present_flag
+    }
+
+    pub fn decoder_model_info_present_flag(&self) -> bool {
+        self.decoder_model_info_present_flag
+    }
+
+    pub fn reduced_still_picture_header(&self) -> bool {
+        self.reduced_still_picture_header
+    }
+
+    pub fn initial_display_delay_present_flag(&self) -> bool {
+        self.initial_display_delay_present_flag
+    }
+
+    pub fn operating_points_cnt_minus_1(&self) -> usize {
+        self.operating_points_cnt_minus_1
+    }
+
+    pub fn operating_points(&self) -> &[OperatingPoint] {
+        &self.operating_points
+    }
+
+    pub fn still_picture(&self) -> bool {
+        self.still_picture
+    }
 
-        osh.color_config =
-            ColorConfig::new(b, seq_profile, &mut state.bit_depth, &mut state.num_planes);
+    pub fn timing_info(&self) -> Option<&TimingInfo> {
+        self.timing_info.as_ref()
+    }
+
+    pub fn decoder_model_info(&self) -> Option<&DecoderModelInfo> {
+        self.decoder_model_info.as_ref()
+    }
+
+    pub fn frame_width_bits_minus_1(&self) -> u64 {
+        self.frame_width_bits_minus_1
+    }
+
+    pub fn frame_height_bits_minus_1(&self) -> u64 {
+        self.frame_height_bits_minus_1
+    }
+
+    pub fn max_frame_width_minus_1(&self) -> u64 {
+        self.max_frame_width_minus_1
+    }
+
+    pub fn max_frame_height_minus_1(&self) -> u64 {
+        self.max_frame_height_minus_1
+    }
+
+    pub fn frame_id_numbers_present_flag(&self) -> bool {
+        self.frame_id_numbers_present_flag
+    }
 
-        osh
+    pub fn delta_frame_id_length_minus_2(&self) -> u64 {
+        self.delta_frame_id_length_minus_2
     }
 
-    fn choose_operating_point() -> usize {
-        todo!("not implemented");
+    pub fn additional_frame_id_length_minus_1(&self) -> u64 {
+        self.additional_frame_id_length_minus_1
+    }
+
+    pub fn use_128x128_superblock(&self) -> bool {
+        self.use_128x128_superblock
+    }
+
+    pub fn enable_filter_intra(&self) -> bool {
+        self.enable_filter_intra
+    }
+
+    pub fn enable_intra_edge_filter(&self) -> bool {
+        self.enable_intra_edge_filter
+    }
+
+    pub fn enable_interintra_compound(&self) -> bool {
+        self.enable_interintra_compound
+    }
+
+    pub fn enable_masked_compound(&self) -> bool {
+        self.enable_masked_compound
+    }
+
+    pub fn enable_warped_motion(&self) -> bool {
+        self.enable_warped_motion
+    }
+
+    pub fn enable_dual_filter(&self) -> bool {
+        self.enable_dual_filter
+    }
+
+    pub fn enable_order_hint(&self) -> bool {
+        self.enable_order_hint
+    }
+
+    pub fn enable_jnt_comp(&self) -> bool {
+        self.enable_jnt_comp
+    }
+
+    pub fn enable_ref_frame_mvs(&self) -> bool {
+        self.enable_ref_frame_mvs
+    }
+
+    pub fn seq_force_screen_content_tools(&self) -> u64 {
+        self.seq_force_screen_content_tools
+    }
+
+    pub fn seq_force_integer_mv(&self) -> u64 {
+        self.seq_force_integer_mv
+    }
+
+    pub fn seq_choose_screen_content_tools(&self) -> bool {
+        self.seq_choose_screen_content_tools
+    }
+
+    pub fn seq_choose_integer_mv(&self) -> bool {
+        self.seq_choose_integer_mv
+    }
+
+    pub fn enable_superres(&self) -> bool {
+        self.enable_superres
+    }
+
+    pub fn enable_cdef(&self) -> bool {
+        self.enable_cdef
+    }
+
+    pub fn enable_restoration(&self) -> bool {
+        self.enable_restoration
+    }
+
+    pub fn film_grain_params_present(&self) -> bool {
+        self.film_grain_params_present
+    }
+
+    pub fn color_config(&self) -> &ColorConfig {
+        &self.color_config
+    }
+}
+
+/// How the decoder picks one of the operating points of a sequence header.
+///
+/// The selection is applied again whenever a new sequence header arrives.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperatingPointSelection {
+    /// The operating point with this index, as recommended by the specification with index 0
+    Index(usize),
+    /// The operating point that decodes the most layers
+    HighestQuality,
+    /// The operating point with the lowest `seq_level_idx` that does not exceed the given one,
+    /// if none fits the one with the lowest level anyway
+    MaxLevel(u64),
+    /// The operating point that decodes the most layers out of this `operating_point_idc` mask,
+    /// bits 0-7 are temporal layers and bits 8-11 are spatial layers
+    LayerMask(u64),
+}
+
+impl Default for OperatingPointSelection {
+    fn default() -> Self {
+        OperatingPointSelection::Index(0)
     }
 }
 
 pub const SELECT_SCREEN_CONTENT_TOOLS: u64 = 2;
 pub const SELECT_INTEGER_MV: u64 = 2;
+
+#[cfg(test)]
+mod tests {
+    use super::{ObuSequenceHeader, OperatingPointSelection};
+    use crate::{bits::bitstream::BitStream, obu::operating_point::OperatingPoint, State};
+
+    #[test]
+    fn obu_sequence_header() {
+        let mut bs = BitStream::new(vec![
+            0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x73, 0xff, 0xe6, 0x01,
+        ]);
+        let mut state = State::default();
+        let sh = ObuSequenceHeader::new(&mut bs, &mut state).unwrap();
+
+        assert_eq!(0, sh.seq_profile);
+        assert!(!sh.reduced_still_picture_header);
+        assert_eq!(0, sh.operating_points_cnt_minus_1);
+        assert_eq!(8, sh.operating_points[0].seq_level_idx);
+        assert_eq!(1919, sh.max_frame_width_minus_1);
+        assert_eq!(1079, sh.max_frame_height_minus_1);
+        assert!(!sh.use_128x128_superblock);
+        assert!(sh.enable_order_hint);
+        assert!(sh.enable_cdef);
+        assert!(!sh.enable_superres);
+        assert_eq!(8, sh.color_config.bit_depth);
+        assert!(sh.color_config.subsampling_x && sh.color_config.subsampling_y);
+
+        assert_eq!(7, state.order_hint_bits);
+        assert_eq!(3, state.num_planes);
+        bs.trailing_bits(8 * 11 - bs.get_position() as u64).unwrap();
+    }
+
+    #[test]
+    fn operating_points_with_decoder_model() {
+        let data = vec![
+            0x04, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x79, 0x18, 0x00, 0x00, 0x00, 0x09,
+            0x09, 0x08, 0x81, 0xa6, 0x90, 0x80, 0x92, 0xff, 0x55, 0x77, 0xf8, 0x6e, 0x7f, 0xfc,
+            0xc0, 0x20,
+        ];
+        let mut bs = BitStream::new(data);
+        let mut state = State::default();
+        let sh = ObuSequenceHeader::new(&mut bs, &mut state).unwrap();
+
+        assert_eq!(2, sh.operating_points.len());
+
+        let op = sh.operating_point(0).unwrap();
+        assert_eq!(0x103, op.operating_point_idc);
+        assert_eq!(1, op.seq_tier);
+        assert!(!op.decoder_model_present_for_this_op);
+        assert_eq!(None, op.decoder_buffer_delay());
+        assert_eq!(Some(3), op.initial_display_delay());
+
+        let op = sh.operating_point(1).unwrap();
+        assert_eq!(0x101, op.operating_point_idc);
+        assert_eq!(0, op.seq_tier);
+        assert_eq!(Some(7), op.decoder_buffer_delay());
+        assert_eq!(Some(15), op.encoder_buffer_delay());
+        assert!(op.low_delay_mode());
+        assert_eq!(None, op.initial_display_delay());
+
+        assert!(sh.operating_point(2).is_none());
+        assert_eq!(0x103, state.operating_point_idc);
+        assert_eq!(1919, sh.max_frame_width_minus_1);
+        bs.trailing_bits(8 * 30 - bs.get_position() as u64).unwrap();
+    }
+
+    /// Two spatial layers with two temporal layers each, followed by the base layer only
+    fn layered_sequence_header() -> ObuSequenceHeader {
+        ObuSequenceHeader {
+            operating_points_cnt_minus_1: 2,
+            operating_points: [(0x303, 12), (0x103, 8), (0x101, 4)]
+                .map(|(operating_point_idc, seq_level_idx)| OperatingPoint {
+                    operating_point_idc,
+                    seq_level_idx,
+                    ..Default::default()
+                })
+                .to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn choose_operating_point() {
+        let sh = layered_sequence_header();
+
+        assert_eq!(
+            0,
+            sh.choose_operating_point(OperatingPointSelection::default())
+        );
+        assert_eq!(
+            2,
+            sh.choose_operating_point(OperatingPointSelection::Index(2))
+        );
+        assert_eq!(
+            0,
+            sh.choose_operating_point(OperatingPointSelection::Index(3))
+        );
+        assert_eq!(
+            0,
+            sh.choose_operating_point(OperatingPointSelection::HighestQuality)
+        );
+        assert_eq!(
+            2,
+            sh.choose_operating_point(OperatingPointSelection::MaxLevel(9))
+        );
+        assert_eq!(
+            2,
+            sh.choose_operating_point(OperatingPointSelection::MaxLevel(0))
+        );
+        assert_eq!(
+            1,
+            sh.choose_operating_point(OperatingPointSelection::LayerMask(0x107))
+        );
+        assert_eq!(
+            0,
+            sh.choose_operating_point(OperatingPointSelection::LayerMask(0x200))
+        );
+    }
+}
@@ -1,53 +1,350 @@
-use crate::{bits::bitstream::BitStream, State};
+use crate::{
+    bits::bitstream::BitStream,
+    error::{CarpError, ErrorKind, Result},
+    State,
+};
 
 use super::{
+    metadata::Metadata,
+    obu_frame_header::ObuFrameHeader,
     obu_header::{ObuHeader, ObuType},
     obu_sequence_header::ObuSequenceHeader,
+    tile_group::TileGroup,
+    tile_list::TileList,
 };
 
-pub struct OpenBitstreamUnit {}
+pub struct OpenBitstreamUnit {
+    pub(crate) header: ObuHeader,
+    pub(crate) payload: ObuPayload,
+}
+
+pub enum ObuPayload {
+    SequenceHeader(ObuSequenceHeader),
+    TemporalDelimiter,
+    FrameHeader(ObuFrameHeader),
+    RedundantFrameHeader(ObuFrameHeader),
+    TileGroup(TileGroup),
+    Frame {
+        frame_header: ObuFrameHeader,
+        tile_group: TileGroup,
+    },
+    Metadata(Metadata),
+    TileList(TileList),
+    Padding,
+    /// Reserved OBUs are skipped
+    Reserved,
+}
+
+impl ObuPayload {
+    /// The frame header of a frame header, redundant frame header or frame OBU
+    pub fn frame_header(&self) -> Option<&ObuFrameHeader> {
+        match self {
+            ObuPayload::FrameHeader(frame_header)
+            | ObuPayload::RedundantFrameHeader(frame_header)
+            | ObuPayload::Frame { frame_header, .. } => Some(frame_header),
+            _ => None,
+        }
+    }
+}
 
 impl OpenBitstreamUnit {
-    pub fn new(bitstream: &mut BitStream, sz: u64, state: &mut State) -> Option<OpenBitstreamUnit> {
-        let header = ObuHeader::new(bitstream);
+    pub fn new(
+        bitstream: &mut BitStream,
+        sz: u64,
+        state: &mut State,
+    ) -> Result<Option<OpenBitstreamUnit>> {
+        let header = ObuHeader::new(bitstream)?;
 
         let obu_size = match header.obu_has_size_field {
-            true => bitstream.leb128(),
-            false => sz - 1 - header.obu_extension_flag as u64,
+            true => bitstream.leb128("obu_size")?,
+            false => sz
+                .checked_sub(1 + header.obu_extension_flag as u64)
+                .ok_or_else(|| bitstream.invalid_value("obu_size", sz))?,
         };
 
-        let _start_position = bitstream.position;
+        let start_position = bitstream.get_position();
 
-        if !matches!(header.obu_type, ObuType::ObuSequenceHeader)
-            && matches!(header.obu_type, ObuType::ObuTemporalDelimiter)
+        if header.obu_type != ObuType::ObuSequenceHeader
+            && header.obu_type != ObuType::ObuTemporalDelimiter
             && state.operating_point_idc != 0
-            && header.obu_extension_flag
         {
-            let in_temporal_layer = ((state.operating_point_idc
-                >> header.obu_extension_header.clone().unwrap().temporal_id)
-                & 1)
-                != 0;
+            if let Some(extension_header) = &header.obu_extension_header {
+                let in_temporal_layer =
+                    ((state.operating_point_idc >> extension_header.temporal_id) & 1) != 0;
+                let in_spatial_layer =
+                    ((state.operating_point_idc >> (extension_header.spatial_id + 8)) & 1) != 0;
 
-            let in_spatial_layer = (state.operating_point_idc
-                >> (header.obu_extension_header.unwrap().spatial_id + 8)
-                & 1)
-                != 0;
+                if !in_temporal_layer || !in_spatial_layer {
+                    OpenBitstreamUnit::drop_obu(bitstream, obu_size)?;
+                    return Ok(None);
+                }
+            }
+        }
+
+        let payload = match header.obu_type {
+            ObuType::ObuSequenceHeader => {
+                let sequence_header = ObuSequenceHeader::new(bitstream, state)?;
+                state.sequence_header = Some(sequence_header.clone());
+                ObuPayload::SequenceHeader(sequence_header)
+            }
+            ObuType::ObuTemporalDelimiter => {
+                state.seen_frame_header = false;
+                ObuPayload::TemporalDelimiter
+            }
+            ObuType::ObuFrameHeader => {
+                ObuPayload::FrameHeader(OpenBitstreamUnit::frame_header(bitstream, state, &header)?)
+            }
+            ObuType::ObuRedundantFrameHeader => ObuPayload::RedundantFrameHeader(
+                OpenBitstreamUnit::frame_header(bitstream, state, &header)?,
+            ),
+            ObuType::ObuTileGroup => {
+                ObuPayload::TileGroup(TileGroup::new(bitstream, obu_size, state)?)
+            }
+            ObuType::ObuMetadata => ObuPayload::Metadata(Metadata::new(bitstream, obu_size)?),
+            ObuType::ObuFrame => {
+                let frame_header = OpenBitstreamUnit::frame_header(bitstream, state, &header)?;
+                bitstream.byte_alignment()?;
 
-            if !in_temporal_layer || !in_spatial_layer {
-                OpenBitstreamUnit::drop_obu(bitstream, obu_size);
-                return None;
+                let header_bytes = (bitstream.get_position() - start_position) as u64 / 8;
+                let sz = obu_size.checked_sub(header_bytes).ok_or_else(|| {
+                    OpenBitstreamUnit::size_mismatch(bitstream, obu_size, start_position)
+                })?;
+
+                ObuPayload::Frame {
+                    frame_header,
+                    tile_group: TileGroup::new(bitstream, sz, state)?,
+                }
+            }
+            ObuType::ObuTileList => ObuPayload::TileList(TileList::new(bitstream)?),
+            ObuType::ObuPadding => {
+                OpenBitstreamUnit::drop_obu(bitstream, obu_size)?;
+                ObuPayload::Padding
             }
+            ObuType::Reserved => {
+                OpenBitstreamUnit::drop_obu(bitstream, obu_size)?;
+                ObuPayload::Reserved
+            }
+        };
+
+        let payload_bits = (bitstream.get_position() - start_position) as u64;
+        let has_trailing_bits = !matches!(
+            payload,
+            ObuPayload::TileGroup(_)
+                | ObuPayload::TileList(_)
+                | ObuPayload::Frame { .. }
+                | ObuPayload::Metadata(_)
+                | ObuPayload::Padding
+                | ObuPayload::Reserved
+        );
+
+        if obu_size > 0 && has_trailing_bits {
+            if payload_bits >= obu_size * 8 {
+                return Err(OpenBitstreamUnit::size_mismatch(
+                    bitstream,
+                    obu_size,
+                    start_position,
+                ));
+            }
+
+            bitstream.trailing_bits(obu_size * 8 - payload_bits)?;
+        } else if payload_bits != obu_size * 8 {
+            return Err(OpenBitstreamUnit::size_mismatch(
+                bitstream,
+                obu_size,
+                start_position,
+            ));
         }
 
-        match header.obu_type {
-            ObuType::ObuSequenceHeader => ObuSequenceHeader::new(bitstream, state),
-            _ => todo!("not implemented"),
+        Ok(Some(OpenBitstreamUnit { header, payload }))
+    }
+
+    fn frame_header(
+        bitstream: &mut BitStream,
+        state: &mut State,
+        header: &ObuHeader,
+    ) -> Result<ObuFrameHeader> {
+        let sequence_header = state
+            .sequence_header
+            .clone()
+            .ok_or_else(|| bitstream.error("frame_header_obu", ErrorKind::MissingSequenceHeader))?;
+
+        ObuFrameHeader::new(bitstream, state, &sequence_header, header)
+    }
+
+    /// The payload did not use exactly `obu_size` bytes, counting partial bytes as used
+    fn size_mismatch(bitstream: &BitStream, obu_size: u64, start_position: usize) -> CarpError {
+        let payload_bits = (bitstream.get_position() - start_position) as u64;
+
+        bitstream.error(
+            "obu_size",
+            ErrorKind::SizeMismatch {
+                expected: obu_size,
+                actual: payload_bits.div_ceil(8),
+            },
+        )
+    }
+
+    fn drop_obu(bitstream: &mut BitStream, obu_size: u64) -> Result<()> {
+        bitstream.skip(obu_size * 8, "obu_size")
+    }
+
+    pub fn header(&self) -> &ObuHeader {
+        &self.header
+    }
+
+    pub fn payload(&self) -> &ObuPayload {
+        &self.payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ObuPayload, OpenBitstreamUnit};
+    use crate::{
+        bits::bitstream::BitStream,
+        error::{CarpError, ErrorKind},
+        test_util, State,
+    };
+
+    const SEQUENCE_HEADER: [u8; 11] = [
+        0x00, 0x00, 0x00, 0x42, 0xab, 0xbf, 0xc3, 0x73, 0xff, 0xe6, 0x01,
+    ];
+
+    fn obu(data: Vec<u8>, state: &mut State) -> crate::error::Result<OpenBitstreamUnit> {
+        let sz = data.len() as u64;
+        Ok(OpenBitstreamUnit::new(&mut BitStream::new(data), sz, state)?.unwrap())
+    }
+
+    #[test]
+    fn sequence_header() {
+        let mut state = State::default();
+        let data = [&[0x0a, 11][..], &SEQUENCE_HEADER].concat();
+
+        let obu = obu(data, &mut state).unwrap();
+        assert!(matches!(obu.payload, ObuPayload::SequenceHeader(_)));
+        assert!(state.sequence_header.is_some());
+    }
+
+    #[test]
+    fn sequence_header_without_trailing_bits() {
+        let mut state = State::default();
+        let mut data = [&[0x0a, 11][..], &SEQUENCE_HEADER].concat();
+        data[12] = 0;
+
+        assert_eq!(
+            Some(CarpError::new(
+                104,
+                "trailing_one_bit",
+                ErrorKind::InvalidValue(0)
+            )),
+            obu(data, &mut state).err()
+        );
+    }
+
+    #[test]
+    fn padding_and_reserved() {
+        let mut state = State::default();
+
+        let padding = obu(vec![0x7a, 2, 0xaa, 0xbb], &mut state).unwrap();
+        assert!(matches!(padding.payload, ObuPayload::Padding));
+
+        let reserved = obu(vec![0x4a, 1, 0xcc], &mut state).unwrap();
+        assert!(matches!(reserved.payload, ObuPayload::Reserved));
+    }
+
+    #[test]
+    fn metadata() {
+        let mut state = State::default();
+
+        let metadata = obu(vec![0x2a, 4, 1, 0x03, 0xe8, 0x80], &mut state).unwrap();
+        match metadata.payload {
+            ObuPayload::Metadata(metadata) => {
+                assert_eq!(1, metadata.metadata_type);
+                assert_eq!(vec![0x03, 0xe8, 0x80], metadata.payload);
+            }
+            _ => panic!("expected metadata"),
+        }
+    }
+
+    #[test]
+    fn tile_list_larger_than_obu_size() {
+        let mut state = State::default();
+        let data = vec![0x42, 5, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0xaa];
+
+        assert_eq!(
+            Some(CarpError::new(
+                96,
+                "obu_size",
+                ErrorKind::SizeMismatch {
+                    expected: 5,
+                    actual: 10
+                }
+            )),
+            obu(data, &mut state).err()
+        );
+    }
+
+    #[test]
+    fn redundant_frame_header_recovers_lost_frame_header() {
+        let mut state = State {
+            sequence_header: Some(test_util::sequence_header(352, 288)),
+            num_planes: 3,
+            ..Default::default()
         };
+        // Key frame header followed by trailing bits
+        let data = vec![
+            0x3a,
+            7,
+            0b0001_0000,
+            0b1000_1100,
+            0b1000_0000,
+            0b0001_0100,
+            0b0000_0000,
+            0b1010_0010,
+            0b1000_0101,
+        ];
+
+        // Without a preceding frame header the redundant one is parsed as the frame header
+        let recovered = obu(data.clone(), &mut state).unwrap();
+        let frame_header = recovered.payload.frame_header().unwrap();
+        assert!(!frame_header.frame_header_copy);
+        assert_eq!(
+            100,
+            frame_header
+                .uncompressed_header
+                .quantization_params
+                .base_q_idx
+        );
 
-        Some(OpenBitstreamUnit {})
+        let redundant = obu(data, &mut state).unwrap();
+        assert!(redundant.payload.frame_header().unwrap().frame_header_copy);
     }
 
-    fn drop_obu(bitstream: &mut BitStream, obu_size: u64) {
-        bitstream.position += (obu_size * 8) as usize;
+    #[test]
+    fn frame_header_without_sequence_header() {
+        let mut state = State::default();
+
+        assert_eq!(
+            Some(CarpError::new(
+                16,
+                "frame_header_obu",
+                ErrorKind::MissingSequenceHeader
+            )),
+            obu(vec![0x1a, 1, 0x80], &mut state).err()
+        );
+    }
+
+    #[test]
+    fn tile_group_without_frame_header() {
+        let mut state = State::default();
+
+        assert_eq!(
+            Some(CarpError::new(
+                16,
+                "tile_group_obu",
+                ErrorKind::MissingFrameHeader
+            )),
+            obu(vec![0x22, 1, 0x00], &mut state).err()
+        );
     }
 }
@@ -1,26 +1,39 @@
-use crate::bits::bitstream::BitStream;
+use crate::{bits::bitstream::BitStream, error::Result};
 
-#[derive(Default)]
-pub struct OperatingParamtersInfo {
-    pub decoder_buffer_delay: Vec<u64>,
-    pub encoder_buffer_delay: Vec<u64>,
-    pub low_delay_mode_flag: Vec<bool>,
+#[derive(Clone, Debug, PartialEq)]
+pub struct OperatingParametersInfo {
+    pub(crate) decoder_buffer_delay: u64,
+    pub(crate) encoder_buffer_delay: u64,
+    pub(crate) low_delay_mode_flag: bool,
 }
 
-impl OperatingParamtersInfo {
+impl OperatingParametersInfo {
     pub fn new(
         b: &mut BitStream,
-        op: usize,
         buffer_delay_length_minus_1: u64,
-    ) -> OperatingParamtersInfo {
+    ) -> Result<OperatingParametersInfo> {
         let n = buffer_delay_length_minus_1 + 1;
 
-        let mut opi = OperatingParamtersInfo::default();
+        let decoder_buffer_delay = b.f(n, "decoder_buffer_delay")?;
+        let encoder_buffer_delay = b.f(n, "encoder_buffer_delay")?;
+        let low_delay_mode_flag = b.f(1, "low_delay_mode_flag")? != 0;
 
-        opi.decoder_buffer_delay.insert(op, b.f(n));
-        opi.encoder_buffer_delay.insert(op, b.f(n));
-        opi.low_delay_mode_flag.insert(op, b.f(1) != 0);
+        Ok(OperatingParametersInfo {
+            decoder_buffer_delay,
+            encoder_buffer_delay,
+            low_delay_mode_flag,
+        })
+    }
+
+    pub fn decoder_buffer_delay(&self) -> u64 {
+        self.decoder_buffer_delay
+    }
+
+    pub fn encoder_buffer_delay(&self) -> u64 {
+        self.encoder_buffer_delay
+    }
 
-        opi
+    pub fn low_delay_mode_flag(&self) -> bool {
+        self.low_delay_mode_flag
     }
 }
@@ -0,0 +1,156 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+use super::{
+    decoder_model_info::DecoderModelInfo, operating_parameters_info::OperatingParametersInfo,
+};
+
+/// One entry of the operating point loop in the sequence header
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatingPoint {
+    pub(crate) operating_point_idc: u64,
+    pub(crate) seq_level_idx: u64,
+    pub(crate) seq_tier: u64,
+    pub(crate) decoder_model_present_for_this_op: bool,
+    pub(crate) operating_parameters_info: Option<OperatingParametersInfo>,
+    pub(crate) initial_display_delay_present_for_this_op: bool,
+    pub(crate) initial_display_delay_minus_1: Option<u64>,
+}
+
+impl OperatingPoint {
+    pub fn new(
+        b: &mut BitStream,
+        decoder_model_info: Option<&DecoderModelInfo>,
+        initial_display_delay_present_flag: bool,
+    ) -> Result<OperatingPoint> {
+        let mut op = OperatingPoint {
+            operating_point_idc: b.f(12, "operating_point_idc")?,
+            seq_level_idx: b.f(5, "seq_level_idx")?,
+            ..Default::default()
+        };
+
+        if op.seq_level_idx > 7 {
+            op.seq_tier = b.f(1, "seq_tier")?;
+        }
+
+        if let Some(decoder_model_info) = decoder_model_info {
+            op.decoder_model_present_for_this_op =
+                b.f(1, "decoder_model_present_for_this_op")? != 0;
+
+            if op.decoder_model_present_for_this_op {
+                op.operating_parameters_info = Some(OperatingParametersInfo::new(
+                    b,
+                    decoder_model_info.buffer_delay_length_minus_1,
+                )?);
+            }
+        }
+
+        if initial_display_delay_present_flag {
+            op.initial_display_delay_present_for_this_op =
+                b.f(1, "initial_display_delay_present_for_this_op")? != 0;
+
+            if op.initial_display_delay_present_for_this_op {
+                op.initial_display_delay_minus_1 = Some(b.f(4, "initial_display_delay_minus_1")?);
+            }
+        }
+
+        Ok(op)
+    }
+
+    /// Whether the OBUs of a temporal and spatial layer are part of this operating point
+    pub fn contains_layer(&self, temporal_id: u64, spatial_id: u64) -> bool {
+        let in_temporal_layer = ((self.operating_point_idc >> temporal_id) & 1) != 0;
+        let in_spatial_layer = ((self.operating_point_idc >> (spatial_id + 8)) & 1) != 0;
+
+        self.operating_point_idc == 0 || (in_temporal_layer && in_spatial_layer)
+    }
+
+    /// The level as major and minor version, like (5, 1), or `None` for the maximum level 31
+    pub fn level(&self) -> Option<(u64, u64)> {
+        match self.seq_level_idx {
+            31 => None,
+            _ => Some((2 + (self.seq_level_idx >> 2), self.seq_level_idx & 3)),
+        }
+    }
+
+    /// Number of frames that should be decoded before the first frame is displayed
+    pub fn initial_display_delay(&self) -> Option<u64> {
+        self.initial_display_delay_minus_1.map(|delay| delay + 1)
+    }
+
+    pub fn decoder_buffer_delay(&self) -> Option<u64> {
+        self.operating_parameters_info
+            .as_ref()
+            .map(|info| info.decoder_buffer_delay)
+    }
+
+    pub fn encoder_buffer_delay(&self) -> Option<u64> {
+        self.operating_parameters_info
+            .as_ref()
+            .map(|info| info.encoder_buffer_delay)
+    }
+
+    pub fn low_delay_mode(&self) -> bool {
+        self.operating_parameters_info
+            .as_ref()
+            .is_some_and(|info| info.low_delay_mode_flag)
+    }
+
+    pub fn operating_point_idc(&self) -> u64 {
+        self.operating_point_idc
+    }
+
+    pub fn seq_level_idx(&self) -> u64 {
+        self.seq_level_idx
+    }
+
+    pub fn seq_tier(&self) -> u64 {
+        self.seq_tier
+    }
+
+    pub fn decoder_model_present_for_this_op(&self) -> bool {
+        self.decoder_model_present_for_this_op
+    }
+
+    pub fn operating_parameters_info(&self) -> Option<&OperatingParametersInfo> {
+        self.operating_parameters_info.as_ref()
+    }
+
+    pub fn initial_display_delay_present_for_this_op(&self) -> bool {
+        self.initial_display_delay_present_for_this_op
+    }
+
+    pub fn initial_display_delay_minus_1(&self) -> Option<u64> {
+        self.initial_display_delay_minus_1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OperatingPoint;
+    use crate::{bits::bitstream::BitStream, obu::decoder_model_info::DecoderModelInfo};
+
+    #[test]
+    fn operating_point() {
+        // idc 0x103, level 9 with tier 1, decoder model with 4 bit delays, display delay 3
+        let mut bs = BitStream::new(vec![0x10, 0x34, 0xef, 0xf9, 0x00]);
+        let decoder_model_info = DecoderModelInfo {
+            buffer_delay_length_minus_1: 3,
+            num_units_in_decoding_tick: 1,
+            buffer_removal_time_length_minus_1: 0,
+            frame_presentation_time_length_minus_1: 0,
+        };
+        let op = OperatingPoint::new(&mut bs, Some(&decoder_model_info), true).unwrap();
+
+        assert_eq!(0x103, op.operating_point_idc);
+        assert_eq!(Some((4, 1)), op.level());
+        assert_eq!(1, op.seq_tier);
+        assert_eq!(Some(7), op.decoder_buffer_delay());
+        assert_eq!(Some(15), op.encoder_buffer_delay());
+        assert!(op.low_delay_mode());
+        assert_eq!(Some(3), op.initial_display_delay());
+        assert!(op.contains_layer(1, 0));
+        assert!(!op.contains_layer(2, 0));
+        assert!(!op.contains_layer(0, 1));
+        assert_eq!(33, bs.get_position());
+    }
+}
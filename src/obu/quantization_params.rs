@@ -0,0 +1,159 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+use super::color_config::ColorConfig;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuantizationParams {
+    pub(crate) base_q_idx: u64,
+    pub(crate) delta_q_y_dc: i64,
+    pub(crate) diff_uv_delta: bool,
+    pub(crate) delta_q_u_dc: i64,
+    pub(crate) delta_q_u_ac: i64,
+    pub(crate) delta_q_v_dc: i64,
+    pub(crate) delta_q_v_ac: i64,
+    pub(crate) using_qmatrix: bool,
+    pub(crate) qm_y: u64,
+    pub(crate) qm_u: u64,
+    pub(crate) qm_v: u64,
+}
+
+impl QuantizationParams {
+    pub fn new(b: &mut BitStream, cc: &ColorConfig, num_planes: u64) -> Result<QuantizationParams> {
+        let mut qp = QuantizationParams {
+            base_q_idx: b.f(8, "base_q_idx")?,
+            delta_q_y_dc: read_delta_q(b)?,
+            ..Default::default()
+        };
+
+        if num_planes > 1 {
+            if cc.separate_uv_delta_q {
+                qp.diff_uv_delta = b.f(1, "diff_uv_delta")? != 0;
+            } else {
+                qp.diff_uv_delta = false;
+            }
+
+            qp.delta_q_u_dc = read_delta_q(b)?;
+            qp.delta_q_u_ac = read_delta_q(b)?;
+
+            if qp.diff_uv_delta {
+                qp.delta_q_v_dc = read_delta_q(b)?;
+                qp.delta_q_v_ac = read_delta_q(b)?;
+            } else {
+                qp.delta_q_v_dc = qp.delta_q_u_dc;
+                qp.delta_q_v_ac = qp.delta_q_u_ac;
+            }
+        }
+
+        qp.using_qmatrix = b.f(1, "using_qmatrix")? != 0;
+        if qp.using_qmatrix {
+            qp.qm_y = b.f(4, "qm_y")?;
+            qp.qm_u = b.f(4, "qm_u")?;
+
+            if !cc.separate_uv_delta_q {
+                qp.qm_v = qp.qm_u;
+            } else {
+                qp.qm_v = b.f(4, "qm_v")?;
+            }
+        }
+
+        Ok(qp)
+    }
+
+    /// Whether all delta quantizers are zero, so that a qindex of 0 means lossless
+    pub fn deltas_are_zero(&self) -> bool {
+        self.delta_q_y_dc == 0
+            && self.delta_q_u_ac == 0
+            && self.delta_q_u_dc == 0
+            && self.delta_q_v_ac == 0
+            && self.delta_q_v_dc == 0
+    }
+
+    pub fn base_q_idx(&self) -> u64 {
+        self.base_q_idx
+    }
+
+    pub fn delta_q_y_dc(&self) -> i64 {
+        self.delta_q_y_dc
+    }
+
+    pub fn diff_uv_delta(&self) -> bool {
+        self.diff_uv_delta
+    }
+
+    pub fn delta_q_u_dc(&self) -> i64 {
+        self.delta_q_u_dc
+    }
+
+    pub fn delta_q_u_ac(&self) -> i64 {
+        self.delta_q_u_ac
+    }
+
+    pub fn delta_q_v_dc(&self) -> i64 {
+        self.delta_q_v_dc
+    }
+
+    pub fn delta_q_v_ac(&self) -> i64 {
+        self.delta_q_v_ac
+    }
+
+    pub fn using_qmatrix(&self) -> bool {
+        self.using_qmatrix
+    }
+
+    pub fn qm_y(&self) -> u64 {
+        self.qm_y
+    }
+
+    pub fn qm_u(&self) -> u64 {
+        self.qm_u
+    }
+
+    pub fn qm_v(&self) -> u64 {
+        self.qm_v
+    }
+}
+
+fn read_delta_q(b: &mut BitStream) -> Result<i64> {
+    let delta_coded = b.f(1, "delta_coded")? != 0;
+
+    match delta_coded {
+        true => b.su(1 + 6, "delta_q"),
+        false => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuantizationParams;
+    use crate::{bits::bitstream::BitStream, obu::color_config::ColorConfig};
+
+    #[test]
+    fn quantization_params() {
+        let cc = ColorConfig {
+            separate_uv_delta_q: true,
+            ..Default::default()
+        };
+
+        // base_q_idx 100, delta_q_y_dc -2, diff_uv_delta with u dc 3 and v ac -1,
+        // using_qmatrix with levels 5, 6 and 7
+        let mut bs = BitStream::new(vec![
+            0b0110_0100,
+            0b1111_1110,
+            0b1100_0001,
+            0b1001_1111,
+            0b1111_0101,
+            0b0110_0111,
+        ]);
+        let qp = QuantizationParams::new(&mut bs, &cc, 3).unwrap();
+
+        assert_eq!(100, qp.base_q_idx);
+        assert_eq!(-2, qp.delta_q_y_dc);
+        assert!(qp.diff_uv_delta);
+        assert_eq!((3, 0), (qp.delta_q_u_dc, qp.delta_q_u_ac));
+        assert_eq!((0, -1), (qp.delta_q_v_dc, qp.delta_q_v_ac));
+        assert!(qp.using_qmatrix);
+        assert_eq!((5, 6, 7), (qp.qm_y, qp.qm_u, qp.qm_v));
+        assert!(!qp.deltas_are_zero());
+        assert_eq!(48, bs.get_position());
+    }
+}
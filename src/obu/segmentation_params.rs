@@ -0,0 +1,200 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+pub const MAX_SEGMENTS: usize = 8;
+pub const SEG_LVL_MAX: usize = 8;
+pub const SEG_LVL_ALT_Q: usize = 0;
+pub const SEG_LVL_REF_FRAME: usize = 5;
+
+const MAX_LOOP_FILTER: i64 = 63;
+
+const SEGMENTATION_FEATURE_BITS: [u64; SEG_LVL_MAX] = [8, 6, 6, 6, 6, 3, 0, 0];
+const SEGMENTATION_FEATURE_SIGNED: [bool; SEG_LVL_MAX] =
+    [true, true, true, true, true, false, false, false];
+const SEGMENTATION_FEATURE_MAX: [i64; SEG_LVL_MAX] = [
+    255,
+    MAX_LOOP_FILTER,
+    MAX_LOOP_FILTER,
+    MAX_LOOP_FILTER,
+    MAX_LOOP_FILTER,
+    7,
+    0,
+    0,
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SegmentationParams {
+    pub(crate) segmentation_enabled: bool,
+    pub(crate) segmentation_update_map: bool,
+    pub(crate) segmentation_temporal_update: bool,
+    pub(crate) segmentation_update_data: bool,
+    pub(crate) feature_enabled: [[bool; SEG_LVL_MAX]; MAX_SEGMENTS],
+    pub(crate) feature_data: [[i64; SEG_LVL_MAX]; MAX_SEGMENTS],
+    pub(crate) seg_id_pre_skip: bool,
+    pub(crate) last_active_seg_id: usize,
+}
+
+impl SegmentationParams {
+    /// Reads segmentation_params().
+    ///
+    /// `previous` holds the features of the primary reference frame, they are kept
+    /// if segmentation is enabled without updating its data.
+    pub fn new(
+        b: &mut BitStream,
+        primary_ref_frame_none: bool,
+        previous: &SegmentationParams,
+    ) -> Result<SegmentationParams> {
+        let mut sp = SegmentationParams {
+            segmentation_enabled: b.f(1, "segmentation_enabled")? != 0,
+            ..Default::default()
+        };
+
+        if sp.segmentation_enabled {
+            if primary_ref_frame_none {
+                sp.segmentation_update_map = true;
+                sp.segmentation_temporal_update = false;
+                sp.segmentation_update_data = true;
+            } else {
+                sp.segmentation_update_map = b.f(1, "segmentation_update_map")? != 0;
+                if sp.segmentation_update_map {
+                    sp.segmentation_temporal_update = b.f(1, "segmentation_temporal_update")? != 0;
+                }
+                sp.segmentation_update_data = b.f(1, "segmentation_update_data")? != 0;
+            }
+
+            if sp.segmentation_update_data {
+                for i in 0..MAX_SEGMENTS {
+                    for j in 0..SEG_LVL_MAX {
+                        sp.feature_enabled[i][j] = b.f(1, "feature_enabled")? != 0;
+
+                        if sp.feature_enabled[i][j] {
+                            let bits_to_read = SEGMENTATION_FEATURE_BITS[j];
+                            let limit = SEGMENTATION_FEATURE_MAX[j];
+
+                            sp.feature_data[i][j] = match SEGMENTATION_FEATURE_SIGNED[j] {
+                                true => b
+                                    .su(1 + bits_to_read, "feature_value")?
+                                    .clamp(-limit, limit),
+                                false => (b.f(bits_to_read, "feature_value")? as i64).min(limit),
+                            };
+                        }
+                    }
+                }
+            } else {
+                sp.feature_enabled = previous.feature_enabled;
+                sp.feature_data = previous.feature_data;
+            }
+        }
+
+        for i in 0..MAX_SEGMENTS {
+            for j in 0..SEG_LVL_MAX {
+                if sp.feature_enabled[i][j] {
+                    sp.last_active_seg_id = i;
+                    if j >= SEG_LVL_REF_FRAME {
+                        sp.seg_id_pre_skip = true;
+                    }
+                }
+            }
+        }
+
+        Ok(sp)
+    }
+
+    pub fn feature_active(&self, segment_id: usize, feature: usize) -> bool {
+        self.segmentation_enabled && self.feature_enabled[segment_id][feature]
+    }
+
+    /// The qindex of a segment without delta quantizers, get_qindex(1, segmentId) in the
+    /// specification
+    pub fn qindex(&self, base_q_idx: u64, segment_id: usize) -> u64 {
+        match self.feature_active(segment_id, SEG_LVL_ALT_Q) {
+            true => {
+                (base_q_idx as i64 + self.feature_data[segment_id][SEG_LVL_ALT_Q]).clamp(0, 255)
+                    as u64
+            }
+            false => base_q_idx,
+        }
+    }
+
+    pub fn segmentation_enabled(&self) -> bool {
+        self.segmentation_enabled
+    }
+
+    pub fn segmentation_update_map(&self) -> bool {
+        self.segmentation_update_map
+    }
+
+    pub fn segmentation_temporal_update(&self) -> bool {
+        self.segmentation_temporal_update
+    }
+
+    pub fn segmentation_update_data(&self) -> bool {
+        self.segmentation_update_data
+    }
+
+    pub fn feature_enabled(&self) -> [[bool; SEG_LVL_MAX]; MAX_SEGMENTS] {
+        self.feature_enabled
+    }
+
+    pub fn feature_data(&self) -> [[i64; SEG_LVL_MAX]; MAX_SEGMENTS] {
+        self.feature_data
+    }
+
+    pub fn seg_id_pre_skip(&self) -> bool {
+        self.seg_id_pre_skip
+    }
+
+    pub fn last_active_seg_id(&self) -> usize {
+        self.last_active_seg_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SegmentationParams, SEG_LVL_ALT_Q, SEG_LVL_REF_FRAME};
+    use crate::bits::bitstream::BitStream;
+
+    #[test]
+    fn segmentation_disabled() {
+        let mut bs = BitStream::new(vec![0]);
+        let sp = SegmentationParams::new(&mut bs, true, &SegmentationParams::default()).unwrap();
+
+        assert!(!sp.segmentation_enabled);
+        assert_eq!(20, sp.qindex(20, 3));
+        assert_eq!(1, bs.get_position());
+    }
+
+    #[test]
+    fn segmentation_update_data() {
+        // Segment 0 has an alt q of -20, segment 1 a reference frame of 3
+        let mut data = vec![0b1111_1101, 0b1000_0000, 0b0000_0001, 0b0110_0000];
+        data.resize(10, 0);
+        let mut bs = BitStream::new(data);
+        let sp = SegmentationParams::new(&mut bs, true, &SegmentationParams::default()).unwrap();
+
+        assert!(sp.segmentation_update_map);
+        assert_eq!(-20, sp.feature_data[0][SEG_LVL_ALT_Q]);
+        assert!(sp.feature_enabled[1][SEG_LVL_REF_FRAME]);
+        assert_eq!(3, sp.feature_data[1][SEG_LVL_REF_FRAME]);
+        assert_eq!(1, sp.last_active_seg_id);
+        assert!(sp.seg_id_pre_skip);
+        assert_eq!(80, sp.qindex(100, 0));
+        assert_eq!(100, sp.qindex(100, 1));
+        assert_eq!(1 + 17 + 11 + 6 * 8, bs.get_position());
+    }
+
+    #[test]
+    fn keep_previous_features() {
+        let mut previous = SegmentationParams::default();
+        previous.feature_enabled[2][SEG_LVL_ALT_Q] = true;
+        previous.feature_data[2][SEG_LVL_ALT_Q] = 12;
+
+        // segmentation_enabled, no map update, no data update
+        let mut bs = BitStream::new(vec![0b1000_0000]);
+        let sp = SegmentationParams::new(&mut bs, false, &previous).unwrap();
+
+        assert!(!sp.segmentation_update_data);
+        assert_eq!(112, sp.qindex(100, 2));
+        assert_eq!(2, sp.last_active_seg_id);
+        assert_eq!(3, bs.get_position());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{bits::bitstream::BitStream, error::Result, State};
+
+use super::obu_frame_header::{
+    ALTREF2_FRAME, ALTREF_FRAME, BWDREF_FRAME, GOLDEN_FRAME, LAST2_FRAME, LAST3_FRAME, LAST_FRAME,
+    NUM_REF_FRAMES, REFS_PER_FRAME,
+};
+
+/// Selects the references of a frame that uses `frame_refs_short_signaling`, from section 7.8.
+///
+/// Only LAST_FRAME and GOLDEN_FRAME are signaled, the other references are chosen by their
+/// order hints relative to the current frame.
+pub fn set_frame_refs(
+    b: &BitStream,
+    state: &State,
+    last_frame_idx: usize,
+    gold_frame_idx: usize,
+) -> Result<[usize; REFS_PER_FRAME]> {
+    let mut refs = FrameRefs::new(state);
+    refs.set(LAST_FRAME, Some(last_frame_idx));
+    refs.set(GOLDEN_FRAME, Some(gold_frame_idx));
+
+    if refs.shifted_order_hints[last_frame_idx] >= refs.cur_frame_hint {
+        return Err(b.invalid_value("last_frame_idx", last_frame_idx as u64));
+    }
+
+    if refs.shifted_order_hints[gold_frame_idx] >= refs.cur_frame_hint {
+        return Err(b.invalid_value("gold_frame_idx", gold_frame_idx as u64));
+    }
+
+    let latest_backward = refs.find_latest_backward();
+    refs.set(ALTREF_FRAME, latest_backward);
+    let earliest_backward = refs.find_earliest_backward();
+    refs.set(BWDREF_FRAME, earliest_backward);
+    let earliest_backward = refs.find_earliest_backward();
+    refs.set(ALTREF2_FRAME, earliest_backward);
+
+    for ref_frame in REF_FRAME_LIST {
+        if refs.ref_frame_idx[ref_frame - LAST_FRAME].is_none() {
+            let latest_forward = refs.find_latest_forward();
+            refs.set(ref_frame, latest_forward);
+        }
+    }
+
+    // The remaining references use the frame with the earliest order hint
+    let earliest = (0..NUM_REF_FRAMES)
+        .min_by_key(|i| refs.shifted_order_hints[*i])
+        .unwrap();
+
+    Ok(refs.ref_frame_idx.map(|idx| idx.unwrap_or(earliest)))
+}
+
+const REF_FRAME_LIST: [usize; REFS_PER_FRAME - 2] = [
+    LAST2_FRAME,
+    LAST3_FRAME,
+    BWDREF_FRAME,
+    ALTREF2_FRAME,
+    ALTREF_FRAME,
+];
+
+struct FrameRefs {
+    ref_frame_idx: [Option<usize>; REFS_PER_FRAME],
+    used_frame: [bool; NUM_REF_FRAMES],
+    cur_frame_hint: i64,
+    shifted_order_hints: [i64; NUM_REF_FRAMES],
+}
+
+impl FrameRefs {
+    fn new(state: &State) -> FrameRefs {
+        let cur_frame_hint = 1 << (state.order_hint_bits - 1);
+
+        FrameRefs {
+            ref_frame_idx: [None; REFS_PER_FRAME],
+            used_frame: [false; NUM_REF_FRAMES],
+            cur_frame_hint,
+            shifted_order_hints: state.ref_order_hint.map(|ref_order_hint| {
+                cur_frame_hint + state.get_relative_dist(ref_order_hint, state.order_hint)
+            }),
+        }
+    }
+
+    fn set(&mut self, ref_frame: usize, idx: Option<usize>) {
+        if let Some(idx) = idx {
+            self.ref_frame_idx[ref_frame - LAST_FRAME] = Some(idx);
+            self.used_frame[idx] = true;
+        }
+    }
+
+    /// Indices of the unused frames with their shifted order hints
+    fn unused(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        (0..NUM_REF_FRAMES)
+            .filter(|i| !self.used_frame[*i])
+            .map(|i| (i, self.shifted_order_hints[i]))
+    }
+
+    fn find_latest_backward(&self) -> Option<usize> {
+        let mut latest = None;
+        for (i, hint) in self.unused() {
+            if hint >= self.cur_frame_hint && latest.is_none_or(|(_, latest)| hint >= latest) {
+                latest = Some((i, hint));
+            }
+        }
+
+        latest.map(|(i, _)| i)
+    }
+
+    fn find_earliest_backward(&self) -> Option<usize> {
+        let mut earliest = None;
+        for (i, hint) in self.unused() {
+            if hint >= self.cur_frame_hint && earliest.is_none_or(|(_, earliest)| hint < earliest) {
+                earliest = Some((i, hint));
+            }
+        }
+
+        earliest.map(|(i, _)| i)
+    }
+
+    fn find_latest_forward(&self) -> Option<usize> {
+        let mut latest = None;
+        for (i, hint) in self.unused() {
+            if hint < self.cur_frame_hint && latest.is_none_or(|(_, latest)| hint >= latest) {
+                latest = Some((i, hint));
+            }
+        }
+
+        latest.map(|(i, _)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::set_frame_refs;
+    use crate::{
+        bits::bitstream::BitStream,
+        error::{CarpError, ErrorKind},
+        test_util::order_hint_state,
+    };
+
+    #[test]
+    fn forward_and_backward_references() {
+        // Slots 0-4 hold past frames, slots 5-7 future frames of a hierarchical group
+        let state = order_hint_state([6, 7, 5, 0, 4, 16, 12, 10], 8);
+        let b = BitStream::new(vec![]);
+
+        let ref_frame_idx = set_frame_refs(&b, &state, 1, 3).unwrap();
+
+        // LAST, LAST2, LAST3, GOLDEN, BWDREF, ALTREF2, ALTREF
+        assert_eq!([1, 0, 2, 3, 7, 6, 5], ref_frame_idx);
+    }
+
+    #[test]
+    fn only_forward_references() {
+        let state = order_hint_state([10, 9, 8, 7, 6, 5, 4, 3], 11);
+        let b = BitStream::new(vec![]);
+
+        let ref_frame_idx = set_frame_refs(&b, &state, 0, 7).unwrap();
+
+        assert_eq!([0, 1, 2, 7, 3, 4, 5], ref_frame_idx);
+    }
+
+    #[test]
+    fn last_frame_in_the_future() {
+        let state = order_hint_state([10, 9, 8, 7, 6, 5, 4, 3], 8);
+        let b = BitStream::new(vec![]);
+
+        assert_eq!(
+            Some(CarpError::new(
+                0,
+                "last_frame_idx",
+                ErrorKind::InvalidValue(0)
+            )),
+            set_frame_refs(&b, &state, 0, 7).err()
+        );
+    }
+}
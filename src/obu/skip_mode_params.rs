@@ -0,0 +1,148 @@
+use crate::{bits::bitstream::BitStream, error::Result, State};
+
+use super::{
+    obu_frame_header::{LAST_FRAME, REFS_PER_FRAME},
+    obu_sequence_header::ObuSequenceHeader,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkipModeParams {
+    pub(crate) skip_mode_present: bool,
+    pub(crate) skip_mode_frame: Option<[usize; 2]>,
+}
+
+impl SkipModeParams {
+    pub fn new(
+        b: &mut BitStream,
+        sh: &ObuSequenceHeader,
+        state: &State,
+        reference_select: bool,
+        ref_frame_idx: &[usize; REFS_PER_FRAME],
+    ) -> Result<SkipModeParams> {
+        let mut smp = SkipModeParams::default();
+
+        if !state.frame_is_intra && reference_select && sh.enable_order_hint {
+            smp.skip_mode_frame = skip_mode_frame(state, ref_frame_idx);
+        }
+
+        if smp.skip_mode_frame.is_some() {
+            smp.skip_mode_present = b.f(1, "skip_mode_present")? != 0;
+        }
+
+        Ok(smp)
+    }
+
+    pub fn skip_mode_present(&self) -> bool {
+        self.skip_mode_present
+    }
+
+    pub fn skip_mode_frame(&self) -> Option<[usize; 2]> {
+        self.skip_mode_frame
+    }
+}
+
+fn skip_mode_frame(state: &State, ref_frame_idx: &[usize; REFS_PER_FRAME]) -> Option<[usize; 2]> {
+    let ref_hints = ref_frame_idx.map(|idx| state.ref_order_hint[idx]);
+
+    let mut forward: Option<(usize, u64)> = None;
+    let mut backward: Option<(usize, u64)> = None;
+
+    for (i, &ref_hint) in ref_hints.iter().enumerate() {
+        let dist = state.get_relative_dist(ref_hint, state.order_hint);
+
+        if dist < 0 {
+            if forward.is_none_or(|(_, hint)| state.get_relative_dist(ref_hint, hint) > 0) {
+                forward = Some((i, ref_hint));
+            }
+        } else if dist > 0
+            && backward.is_none_or(|(_, hint)| state.get_relative_dist(ref_hint, hint) < 0)
+        {
+            backward = Some((i, ref_hint));
+        }
+    }
+
+    let (forward_idx, forward_hint) = forward?;
+
+    let other_idx = match backward {
+        Some((backward_idx, _)) => backward_idx,
+        None => {
+            let mut second_forward: Option<(usize, u64)> = None;
+
+            for (i, &ref_hint) in ref_hints.iter().enumerate() {
+                if state.get_relative_dist(ref_hint, forward_hint) < 0
+                    && second_forward
+                        .is_none_or(|(_, hint)| state.get_relative_dist(ref_hint, hint) > 0)
+                {
+                    second_forward = Some((i, ref_hint));
+                }
+            }
+
+            second_forward?.0
+        }
+    };
+
+    Some([
+        LAST_FRAME + forward_idx.min(other_idx),
+        LAST_FRAME + forward_idx.max(other_idx),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipModeParams;
+    use crate::{
+        bits::bitstream::BitStream,
+        obu::{
+            obu_frame_header::{ALTREF2_FRAME, ALTREF_FRAME, BWDREF_FRAME, LAST2_FRAME},
+            obu_sequence_header::ObuSequenceHeader,
+        },
+        test_util::{order_hint_state, sequence_header},
+    };
+
+    #[test]
+    fn forward_and_backward() {
+        let sh = ObuSequenceHeader {
+            enable_order_hint: true,
+            ..sequence_header(352, 288)
+        };
+        let state = order_hint_state([8, 6, 4, 16, 12, 0, 0, 0], 10);
+
+        let mut bs = BitStream::new(vec![0b1000_0000]);
+        let smp = SkipModeParams::new(&mut bs, &sh, &state, true, &[1, 0, 2, 5, 4, 3, 6]).unwrap();
+
+        assert!(smp.skip_mode_present);
+        assert_eq!(Some([LAST2_FRAME, BWDREF_FRAME]), smp.skip_mode_frame);
+        assert_eq!(1, bs.get_position());
+    }
+
+    #[test]
+    fn two_forward() {
+        let sh = ObuSequenceHeader {
+            enable_order_hint: true,
+            ..sequence_header(352, 288)
+        };
+        let state = order_hint_state([8, 6, 4, 2, 0, 0, 0, 0], 10);
+
+        let mut bs = BitStream::new(vec![0b0000_0000]);
+        let smp = SkipModeParams::new(&mut bs, &sh, &state, true, &[3, 2, 2, 2, 2, 1, 0]).unwrap();
+
+        assert!(!smp.skip_mode_present);
+        assert_eq!(Some([ALTREF2_FRAME, ALTREF_FRAME]), smp.skip_mode_frame);
+        assert_eq!(1, bs.get_position());
+    }
+
+    #[test]
+    fn single_reference() {
+        let sh = ObuSequenceHeader {
+            enable_order_hint: true,
+            ..sequence_header(352, 288)
+        };
+        let state = order_hint_state([8; 8], 10);
+
+        let mut bs = BitStream::new(vec![0b1000_0000]);
+        let smp = SkipModeParams::new(&mut bs, &sh, &state, true, &[0; 7]).unwrap();
+
+        assert_eq!(None, smp.skip_mode_frame);
+        assert_eq!(0, bs.get_position());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    bits::bitstream::BitStream,
+    error::{ErrorKind, Result},
+    State,
+};
+
+/// Tile data is not decoded, only the tiles of the group and their sizes are recorded
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileGroup {
+    pub(crate) size: u64,
+    pub(crate) tg_start: u64,
+    pub(crate) tg_end: u64,
+    pub(crate) tile_sizes: Vec<u64>,
+}
+
+impl TileGroup {
+    /// Reads tile_group_obu() with the tile info of the current frame header
+    pub fn new(b: &mut BitStream, sz: u64, state: &mut State) -> Result<TileGroup> {
+        let ti = &state.tile_info;
+        let num_tiles = ti.tile_cols * ti.tile_rows;
+        if !state.seen_frame_header || num_tiles == 0 {
+            return Err(b.error("tile_group_obu", ErrorKind::MissingFrameHeader));
+        }
+
+        let start_position = b.get_position();
+
+        let mut tile_start_and_end_present_flag = false;
+        if num_tiles > 1 {
+            tile_start_and_end_present_flag = b.f(1, "tile_start_and_end_present_flag")? != 0;
+        }
+
+        let (tg_start, tg_end) = if num_tiles == 1 || !tile_start_and_end_present_flag {
+            (0, num_tiles - 1)
+        } else {
+            let tile_bits = ti.tile_cols_log2 + ti.tile_rows_log2;
+            let tg_start = b.f(tile_bits, "tg_start")?;
+            let tg_end = b.f(tile_bits, "tg_end")?;
+
+            if tg_end < tg_start || tg_end >= num_tiles {
+                return Err(b.invalid_value("tg_end", tg_end));
+            }
+
+            (tg_start, tg_end)
+        };
+
+        b.byte_alignment()?;
+
+        let header_bytes = (b.get_position() - start_position) as u64 / 8;
+        let mut remaining = sz
+            .checked_sub(header_bytes)
+            .ok_or_else(|| b.invalid_value("obu_size", sz))?;
+
+        let mut tile_sizes = vec![];
+        for tile_num in tg_start..=tg_end {
+            let tile_size = if tile_num == tg_end {
+                remaining
+            } else {
+                let tile_size = b.le(ti.tile_size_bytes, "tile_size_minus_1")? + 1;
+                remaining = remaining
+                    .checked_sub(tile_size + ti.tile_size_bytes)
+                    .ok_or_else(|| b.invalid_value("tile_size_minus_1", tile_size - 1))?;
+                tile_size
+            };
+
+            b.skip(tile_size * 8, "tile_data")?;
+            tile_sizes.push(tile_size);
+        }
+
+        if tg_end + 1 == num_tiles {
+            state.seen_frame_header = false;
+        }
+
+        Ok(TileGroup {
+            size: sz,
+            tg_start,
+            tg_end,
+            tile_sizes,
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn tg_start(&self) -> u64 {
+        self.tg_start
+    }
+
+    pub fn tg_end(&self) -> u64 {
+        self.tg_end
+    }
+
+    /// Size in bytes of every tile from `tg_start` to `tg_end`
+    pub fn tile_sizes(&self) -> &[u64] {
+        &self.tile_sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TileGroup;
+    use crate::{
+        bits::bitstream::BitStream,
+        error::{CarpError, ErrorKind},
+        obu::tile_info::TileInfo,
+        State,
+    };
+
+    fn state() -> State {
+        // 2x2 tiles with 2 byte tile sizes
+        State {
+            seen_frame_header: true,
+            tile_info: TileInfo {
+                tile_cols_log2: 1,
+                tile_rows_log2: 1,
+                tile_cols: 2,
+                tile_rows: 2,
+                tile_size_bytes: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tile_groups() {
+        let mut state = state();
+
+        // Tiles 0 and 1 with 3 and 2 bytes
+        let data = vec![0b1000_1000, 2, 0, 0xaa, 0xbb, 0xcc, 0xdd, 0xee];
+        let mut bs = BitStream::new(data);
+        let tg = TileGroup::new(&mut bs, 8, &mut state).unwrap();
+
+        assert_eq!((0, 1), (tg.tg_start, tg.tg_end));
+        assert_eq!(vec![3, 2], tg.tile_sizes);
+        assert!(state.seen_frame_header);
+
+        // Tiles 2 and 3 end the frame
+        let data = vec![0b1101_1000, 0, 0, 0xaa, 0xbb];
+        let mut bs = BitStream::new(data);
+        let tg = TileGroup::new(&mut bs, 5, &mut state).unwrap();
+
+        assert_eq!((2, 3), (tg.tg_start, tg.tg_end));
+        assert_eq!(vec![1, 1], tg.tile_sizes);
+        assert!(!state.seen_frame_header);
+        assert_eq!(40, bs.get_position());
+    }
+
+    #[test]
+    fn tile_size_larger_than_obu() {
+        let mut state = state();
+
+        let data = vec![0b0000_0000, 9, 0, 0xaa];
+        let mut bs = BitStream::new(data);
+
+        assert_eq!(
+            Some(CarpError::new(
+                24,
+                "tile_size_minus_1",
+                ErrorKind::InvalidValue(9)
+            )),
+            TileGroup::new(&mut bs, 4, &mut state).err()
+        );
+    }
+}
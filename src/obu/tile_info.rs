@@ -0,0 +1,271 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+use super::{frame_size::FrameSize, obu_sequence_header::ObuSequenceHeader};
+
+const MAX_TILE_WIDTH: u64 = 4096;
+const MAX_TILE_AREA: u64 = 4096 * 2304;
+const MAX_TILE_ROWS: u64 = 64;
+const MAX_TILE_COLS: u64 = 64;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileInfo {
+    pub(crate) uniform_tile_spacing_flag: bool,
+    pub(crate) tile_cols_log2: u64,
+    pub(crate) tile_rows_log2: u64,
+    pub(crate) tile_cols: u64,
+    pub(crate) tile_rows: u64,
+    pub(crate) mi_col_starts: Vec<u64>,
+    pub(crate) mi_row_starts: Vec<u64>,
+    pub(crate) context_update_tile_id: u64,
+    pub(crate) tile_size_bytes: u64,
+}
+
+impl TileInfo {
+    pub fn new(b: &mut BitStream, sh: &ObuSequenceHeader, fs: &FrameSize) -> Result<TileInfo> {
+        let mut ti = TileInfo::default();
+
+        let (sb_cols, sb_rows, sb_shift) = match sh.use_128x128_superblock {
+            true => ((fs.mi_cols + 31) >> 5, (fs.mi_rows + 31) >> 5, 5),
+            false => ((fs.mi_cols + 15) >> 4, (fs.mi_rows + 15) >> 4, 4),
+        };
+        let sb_size = sb_shift + 2;
+
+        if sb_cols == 0 {
+            return Err(b.invalid_value("mi_cols", fs.mi_cols));
+        }
+        if sb_rows == 0 {
+            return Err(b.invalid_value("mi_rows", fs.mi_rows));
+        }
+
+        let max_tile_width_sb = MAX_TILE_WIDTH >> sb_size;
+        let mut max_tile_area_sb = MAX_TILE_AREA >> (2 * sb_size);
+        let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
+        let max_log2_tile_cols = tile_log2(1, sb_cols.min(MAX_TILE_COLS));
+        let max_log2_tile_rows = tile_log2(1, sb_rows.min(MAX_TILE_ROWS));
+        let min_log2_tiles = min_log2_tile_cols.max(tile_log2(max_tile_area_sb, sb_rows * sb_cols));
+
+        ti.uniform_tile_spacing_flag = b.f(1, "uniform_tile_spacing_flag")? != 0;
+
+        if ti.uniform_tile_spacing_flag {
+            ti.tile_cols_log2 = min_log2_tile_cols;
+            while ti.tile_cols_log2 < max_log2_tile_cols {
+                if b.f(1, "increment_tile_cols_log2")? == 0 {
+                    break;
+                }
+                ti.tile_cols_log2 += 1;
+            }
+
+            let tile_width_sb = (sb_cols + (1 << ti.tile_cols_log2) - 1) >> ti.tile_cols_log2;
+            ti.mi_col_starts = (0..sb_cols)
+                .step_by(tile_width_sb as usize)
+                .map(|start_sb| start_sb << sb_shift)
+                .collect();
+
+            let min_log2_tile_rows = min_log2_tiles.saturating_sub(ti.tile_cols_log2);
+            ti.tile_rows_log2 = min_log2_tile_rows;
+            while ti.tile_rows_log2 < max_log2_tile_rows {
+                if b.f(1, "increment_tile_rows_log2")? == 0 {
+                    break;
+                }
+                ti.tile_rows_log2 += 1;
+            }
+
+            let tile_height_sb = (sb_rows + (1 << ti.tile_rows_log2) - 1) >> ti.tile_rows_log2;
+            ti.mi_row_starts = (0..sb_rows)
+                .step_by(tile_height_sb as usize)
+                .map(|start_sb| start_sb << sb_shift)
+                .collect();
+        } else {
+            let mut widest_tile_sb = 0;
+            let mut start_sb = 0;
+            while start_sb < sb_cols {
+                ti.mi_col_starts.push(start_sb << sb_shift);
+
+                let max_width = (sb_cols - start_sb).min(max_tile_width_sb);
+                let size_sb = b.ns(max_width, "width_in_sbs_minus_1")? + 1;
+                widest_tile_sb = widest_tile_sb.max(size_sb);
+                start_sb += size_sb;
+            }
+
+            if min_log2_tiles > 0 {
+                max_tile_area_sb = (sb_rows * sb_cols) >> (min_log2_tiles + 1);
+            } else {
+                max_tile_area_sb = sb_rows * sb_cols;
+            }
+            let max_tile_height_sb = (max_tile_area_sb / widest_tile_sb).max(1);
+
+            start_sb = 0;
+            while start_sb < sb_rows {
+                ti.mi_row_starts.push(start_sb << sb_shift);
+
+                let max_height = (sb_rows - start_sb).min(max_tile_height_sb);
+                start_sb += b.ns(max_height, "height_in_sbs_minus_1")? + 1;
+            }
+        }
+
+        ti.tile_cols = ti.mi_col_starts.len() as u64;
+        ti.tile_rows = ti.mi_row_starts.len() as u64;
+        ti.mi_col_starts.push(fs.mi_cols);
+        ti.mi_row_starts.push(fs.mi_rows);
+
+        if !ti.uniform_tile_spacing_flag {
+            ti.tile_cols_log2 = tile_log2(1, ti.tile_cols);
+            ti.tile_rows_log2 = tile_log2(1, ti.tile_rows);
+        }
+
+        if ti.tile_cols_log2 > 0 || ti.tile_rows_log2 > 0 {
+            ti.context_update_tile_id = b.f(
+                ti.tile_rows_log2 + ti.tile_cols_log2,
+                "context_update_tile_id",
+            )?;
+            if ti.context_update_tile_id >= ti.tile_cols * ti.tile_rows {
+                return Err(b.invalid_value("context_update_tile_id", ti.context_update_tile_id));
+            }
+
+            ti.tile_size_bytes = b.f(2, "tile_size_bytes_minus_1")? + 1;
+        } else {
+            ti.context_update_tile_id = 0;
+        }
+
+        Ok(ti)
+    }
+
+    pub fn uniform_tile_spacing_flag(&self) -> bool {
+        self.uniform_tile_spacing_flag
+    }
+
+    pub fn tile_cols_log2(&self) -> u64 {
+        self.tile_cols_log2
+    }
+
+    pub fn tile_rows_log2(&self) -> u64 {
+        self.tile_rows_log2
+    }
+
+    pub fn tile_cols(&self) -> u64 {
+        self.tile_cols
+    }
+
+    pub fn tile_rows(&self) -> u64 {
+        self.tile_rows
+    }
+
+    /// Start of every tile column in units of 4x4 luma samples, followed by MiCols
+    pub fn mi_col_starts(&self) -> &[u64] {
+        &self.mi_col_starts
+    }
+
+    /// Start of every tile row in units of 4x4 luma samples, followed by MiRows
+    pub fn mi_row_starts(&self) -> &[u64] {
+        &self.mi_row_starts
+    }
+
+    pub fn context_update_tile_id(&self) -> u64 {
+        self.context_update_tile_id
+    }
+
+    /// Number of bytes of each tile size in a tile group, only present with more than one tile
+    pub fn tile_size_bytes(&self) -> u64 {
+        self.tile_size_bytes
+    }
+}
+
+/// Smallest k such that `blk_size << k` is at least `target`
+fn tile_log2(blk_size: u64, target: u64) -> u64 {
+    let mut k = 0;
+    while (blk_size << k) < target {
+        k += 1;
+    }
+
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TileInfo;
+    use crate::{
+        bits::bitstream::BitStream,
+        error::{CarpError, ErrorKind},
+        obu::{frame_size::FrameSize, obu_sequence_header::ObuSequenceHeader},
+    };
+
+    fn frame_size_1080p() -> FrameSize {
+        FrameSize {
+            frame_width: 1920,
+            frame_height: 1080,
+            mi_cols: 480,
+            mi_rows: 270,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn single_tile() {
+        let sh = ObuSequenceHeader::default();
+
+        // uniform_tile_spacing_flag, no increments
+        let mut bs = BitStream::new(vec![0b1000_0000]);
+        let ti = TileInfo::new(&mut bs, &sh, &frame_size_1080p()).unwrap();
+
+        assert_eq!((1, 1), (ti.tile_cols, ti.tile_rows));
+        assert_eq!(vec![0, 480], ti.mi_col_starts);
+        assert_eq!(vec![0, 270], ti.mi_row_starts);
+        assert_eq!(3, bs.get_position());
+    }
+
+    #[test]
+    fn uniform_tiles() {
+        let sh = ObuSequenceHeader::default();
+
+        // uniform_tile_spacing_flag, two column increments, one row increment,
+        // context_update_tile_id 5, tile_size_bytes_minus_1 3
+        let mut bs = BitStream::new(vec![0b1110_1010, 0b1110_0000]);
+        let ti = TileInfo::new(&mut bs, &sh, &frame_size_1080p()).unwrap();
+
+        assert_eq!((2, 1), (ti.tile_cols_log2, ti.tile_rows_log2));
+        assert_eq!((4, 2), (ti.tile_cols, ti.tile_rows));
+        assert_eq!(vec![0, 128, 256, 384, 480], ti.mi_col_starts);
+        assert_eq!(vec![0, 144, 270], ti.mi_row_starts);
+        assert_eq!(5, ti.context_update_tile_id);
+        assert_eq!(4, ti.tile_size_bytes);
+        assert_eq!(11, bs.get_position());
+    }
+
+    #[test]
+    fn non_uniform_tiles() {
+        let sh = ObuSequenceHeader::default();
+
+        // Columns of 20 and 10 superblocks, rows of 10 and 7 superblocks,
+        // context_update_tile_id 0, tile_size_bytes_minus_1 0
+        let mut bs = BitStream::new(vec![0b0101_0111, 0b1110_0111, 0b1000_0000]);
+        let ti = TileInfo::new(&mut bs, &sh, &frame_size_1080p()).unwrap();
+
+        assert_eq!((2, 2), (ti.tile_cols, ti.tile_rows));
+        assert_eq!(vec![0, 320, 480], ti.mi_col_starts);
+        assert_eq!(vec![0, 160, 270], ti.mi_row_starts);
+        assert_eq!((1, 1), (ti.tile_cols_log2, ti.tile_rows_log2));
+        assert_eq!(1, ti.tile_size_bytes);
+        assert_eq!(21, bs.get_position());
+    }
+
+    #[test]
+    fn zero_frame_size() {
+        let sh = ObuSequenceHeader::default();
+
+        let mut bs = BitStream::new(vec![0b1000_0000]);
+        assert_eq!(
+            Some(CarpError::new(0, "mi_cols", ErrorKind::InvalidValue(0))),
+            TileInfo::new(&mut bs, &sh, &FrameSize::default()).err()
+        );
+
+        let fs = FrameSize {
+            mi_rows: 0,
+            ..frame_size_1080p()
+        };
+        let mut bs = BitStream::new(vec![0b0000_0000]);
+        assert_eq!(
+            Some(CarpError::new(0, "mi_rows", ErrorKind::InvalidValue(0))),
+            TileInfo::new(&mut bs, &sh, &fs).err()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::ops::Range;
+
+use super::{frame_size::FrameSize, tile_info::TileInfo};
+
+/// Position and size of the tiles of a frame, derived from its tile_info()
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileLayout {
+    pub(crate) tile_cols: u64,
+    pub(crate) tile_rows: u64,
+    pub(crate) mi_col_starts: Vec<u64>,
+    pub(crate) mi_row_starts: Vec<u64>,
+    pub(crate) sb_shift: u64,
+    pub(crate) frame_width: u64,
+    pub(crate) frame_height: u64,
+}
+
+impl TileLayout {
+    pub fn new(ti: &TileInfo, use_128x128_superblock: bool, fs: &FrameSize) -> TileLayout {
+        TileLayout {
+            tile_cols: ti.tile_cols,
+            tile_rows: ti.tile_rows,
+            mi_col_starts: ti.mi_col_starts.clone(),
+            mi_row_starts: ti.mi_row_starts.clone(),
+            sb_shift: match use_128x128_superblock {
+                true => 5,
+                false => 4,
+            },
+            frame_width: fs.frame_width,
+            frame_height: fs.frame_height,
+        }
+    }
+
+    pub fn num_tiles(&self) -> u64 {
+        self.tile_cols * self.tile_rows
+    }
+
+    /// Superblock columns and rows covered by tile `tile_num`, tiles are numbered in raster order
+    pub fn superblocks(&self, tile_num: u64) -> Option<(Range<u64>, Range<u64>)> {
+        let (cols, rows) = self.mi_ranges(tile_num)?;
+        let sb_range = |mi: Range<u64>| {
+            (mi.start >> self.sb_shift)..((mi.end + (1 << self.sb_shift) - 1) >> self.sb_shift)
+        };
+
+        Some((sb_range(cols), sb_range(rows)))
+    }
+
+    /// Position of the top left luma sample of tile `tile_num`
+    pub fn tile_position(&self, tile_num: u64) -> Option<(u64, u64)> {
+        let (cols, rows) = self.mi_ranges(tile_num)?;
+
+        Some((cols.start * 4, rows.start * 4))
+    }
+
+    /// Width and height of tile `tile_num` in luma samples, tiles at the right and bottom
+    /// edge are cropped to the frame size
+    pub fn tile_size(&self, tile_num: u64) -> Option<(u64, u64)> {
+        let (cols, rows) = self.mi_ranges(tile_num)?;
+
+        Some((
+            (cols.end * 4).min(self.frame_width) - cols.start * 4,
+            (rows.end * 4).min(self.frame_height) - rows.start * 4,
+        ))
+    }
+
+    fn mi_ranges(&self, tile_num: u64) -> Option<(Range<u64>, Range<u64>)> {
+        if tile_num >= self.num_tiles() {
+            return None;
+        }
+
+        let tile_row = (tile_num / self.tile_cols) as usize;
+        let tile_col = (tile_num % self.tile_cols) as usize;
+
+        Some((
+            self.mi_col_starts[tile_col]..self.mi_col_starts[tile_col + 1],
+            self.mi_row_starts[tile_row]..self.mi_row_starts[tile_row + 1],
+        ))
+    }
+
+    pub fn tile_cols(&self) -> u64 {
+        self.tile_cols
+    }
+
+    pub fn tile_rows(&self) -> u64 {
+        self.tile_rows
+    }
+
+    /// Start of every tile column in units of 4x4 luma samples, followed by MiCols
+    pub fn mi_col_starts(&self) -> &[u64] {
+        &self.mi_col_starts
+    }
+
+    /// Start of every tile row in units of 4x4 luma samples, followed by MiRows
+    pub fn mi_row_starts(&self) -> &[u64] {
+        &self.mi_row_starts
+    }
+
+    /// log2 of the superblock size in units of 4x4 luma samples
+    pub fn sb_shift(&self) -> u64 {
+        self.sb_shift
+    }
+
+    pub fn frame_width(&self) -> u64 {
+        self.frame_width
+    }
+
+    pub fn frame_height(&self) -> u64 {
+        self.frame_height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TileLayout;
+    use crate::obu::{frame_size::FrameSize, tile_info::TileInfo};
+
+    #[test]
+    fn tile_layout() {
+        // 1080p frame with two tile columns of 20 and 10 superblocks and two tile rows of
+        // 10 and 7 superblocks
+        let ti = TileInfo {
+            tile_cols: 2,
+            tile_rows: 2,
+            mi_col_starts: vec![0, 320, 480],
+            mi_row_starts: vec![0, 160, 270],
+            ..Default::default()
+        };
+        let fs = FrameSize {
+            frame_width: 1920,
+            frame_height: 1080,
+            ..Default::default()
+        };
+        let layout = TileLayout::new(&ti, false, &fs);
+
+        assert_eq!(4, layout.num_tiles());
+        assert_eq!(Some((0..20, 0..10)), layout.superblocks(0));
+        assert_eq!(Some((20..30, 10..17)), layout.superblocks(3));
+        assert_eq!(Some((1280, 640)), layout.tile_position(3));
+        assert_eq!(Some((1280, 640)), layout.tile_size(0));
+        assert_eq!(Some((640, 440)), layout.tile_size(3));
+        assert_eq!(None, layout.tile_size(4));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{bits::bitstream::BitStream, error::Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileList {
+    pub(crate) output_frame_width_in_tiles_minus_1: u64,
+    pub(crate) output_frame_height_in_tiles_minus_1: u64,
+    pub(crate) tile_count_minus_1: u64,
+    pub(crate) tile_list_entries: Vec<TileListEntry>,
+}
+
+impl TileList {
+    pub fn new(b: &mut BitStream) -> Result<TileList> {
+        let output_frame_width_in_tiles_minus_1 = b.f(8, "output_frame_width_in_tiles_minus_1")?;
+        let output_frame_height_in_tiles_minus_1 =
+            b.f(8, "output_frame_height_in_tiles_minus_1")?;
+        let tile_count_minus_1 = b.f(16, "tile_count_minus_1")?;
+
+        let mut tile_list_entries = Vec::new();
+        for _ in 0..=tile_count_minus_1 {
+            tile_list_entries.push(TileListEntry::new(b)?);
+        }
+
+        Ok(TileList {
+            output_frame_width_in_tiles_minus_1,
+            output_frame_height_in_tiles_minus_1,
+            tile_count_minus_1,
+            tile_list_entries,
+        })
+    }
+
+    pub fn output_frame_width_in_tiles_minus_1(&self) -> u64 {
+        self.output_frame_width_in_tiles_minus_1
+    }
+
+    pub fn output_frame_height_in_tiles_minus_1(&self) -> u64 {
+        self.output_frame_height_in_tiles_minus_1
+    }
+
+    pub fn tile_count_minus_1(&self) -> u64 {
+        self.tile_count_minus_1
+    }
+
+    pub fn tile_list_entries(&self) -> &[TileListEntry] {
+        &self.tile_list_entries
+    }
+}
+
+/// The coded tile data is skipped, only its size is recorded
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileListEntry {
+    pub(crate) anchor_frame_idx: u64,
+    pub(crate) anchor_tile_row: u64,
+    pub(crate) anchor_tile_col: u64,
+    pub(crate) tile_data_size_minus_1: u64,
+}
+
+impl TileListEntry {
+    pub fn new(b: &mut BitStream) -> Result<TileListEntry> {
+        let anchor_frame_idx = b.f(8, "anchor_frame_idx")?;
+        let anchor_tile_row = b.f(8, "anchor_tile_row")?;
+        let anchor_tile_col = b.f(8, "anchor_tile_col")?;
+        let tile_data_size_minus_1 = b.f(16, "tile_data_size_minus_1")?;
+
+        b.skip(8 * (tile_data_size_minus_1 + 1), "coded_tile_data")?;
+
+        Ok(TileListEntry {
+            anchor_frame_idx,
+            anchor_tile_row,
+            anchor_tile_col,
+            tile_data_size_minus_1,
+        })
+    }
+
+    pub fn anchor_frame_idx(&self) -> u64 {
+        self.anchor_frame_idx
+    }
+
+    pub fn anchor_tile_row(&self) -> u64 {
+        self.anchor_tile_row
+    }
+
+    pub fn anchor_tile_col(&self) -> u64 {
+        self.anchor_tile_col
+    }
+
+    pub fn tile_data_size_minus_1(&self) -> u64 {
+        self.tile_data_size_minus_1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TileList;
+    use crate::bits::bitstream::BitStream;
+
+    #[test]
+    fn tile_list() {
+        let mut bs = BitStream::new(vec![
+            1, 0, 0, 1, // 2x1 tiles, two entries
+            3, 0, 1, 0, 1, 0xaa, 0xbb, // two bytes of tile data
+            4, 0, 0, 0, 0, 0xcc, // one byte of tile data
+        ]);
+        let tile_list = TileList::new(&mut bs).unwrap();
+
+        assert_eq!(1, tile_list.output_frame_width_in_tiles_minus_1);
+        assert_eq!(0, tile_list.output_frame_height_in_tiles_minus_1);
+        assert_eq!(2, tile_list.tile_list_entries.len());
+        assert_eq!(3, tile_list.tile_list_entries[0].anchor_frame_idx);
+        assert_eq!(1, tile_list.tile_list_entries[0].anchor_tile_col);
+        assert_eq!(1, tile_list.tile_list_entries[0].tile_data_size_minus_1);
+        assert_eq!(4, tile_list.tile_list_entries[1].anchor_frame_idx);
+        assert!(!bs.more_data_in_bitstream());
+    }
+}
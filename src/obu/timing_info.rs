@@ -1,28 +1,89 @@
-use crate::bits::bitstream::BitStream;
+use crate::{bits::bitstream::BitStream, error::Result};
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct TimingInfo {
-    pub num_units_in_display_tick: u64,
-    pub time_scale: u64,
-    pub equal_picture_interval: bool,
-    pub num_ticks_per_picture_minus_1: u64,
+    pub(crate) num_units_in_display_tick: u64,
+    pub(crate) time_scale: u64,
+    pub(crate) equal_picture_interval: bool,
+    pub(crate) num_ticks_per_picture_minus_1: u64,
 }
 
 impl TimingInfo {
-    pub fn new(bitstream: &mut BitStream) -> TimingInfo {
-        let num_units_in_display_tick = bitstream.f(32);
-        let time_scale = bitstream.f(32);
-        let equal_picture_interval = bitstream.f(1) != 0;
+    pub fn new(bitstream: &mut BitStream) -> Result<TimingInfo> {
+        let num_units_in_display_tick = bitstream.f(32, "num_units_in_display_tick")?;
+        if num_units_in_display_tick == 0 {
+            return Err(bitstream.invalid_value("num_units_in_display_tick", 0));
+        }
+
+        let time_scale = bitstream.f(32, "time_scale")?;
+        if time_scale == 0 {
+            return Err(bitstream.invalid_value("time_scale", 0));
+        }
+
+        let equal_picture_interval = bitstream.f(1, "equal_picture_interval")? != 0;
 
         let num_ticks_per_picture_minus_1 = match equal_picture_interval {
-            true => bitstream.uvlc(),
+            true => bitstream.uvlc("num_ticks_per_picture_minus_1")?,
             false => 0,
         };
 
-        TimingInfo {
+        Ok(TimingInfo {
             num_units_in_display_tick,
             time_scale,
             equal_picture_interval,
             num_ticks_per_picture_minus_1,
-        }
+        })
+    }
+
+    /// Converts a `frame_presentation_time` in display clock ticks to seconds
+    pub fn presentation_time(&self, frame_presentation_time: u64) -> f64 {
+        frame_presentation_time as f64 * self.num_units_in_display_tick as f64
+            / self.time_scale as f64
+    }
+
+    pub fn num_units_in_display_tick(&self) -> u64 {
+        self.num_units_in_display_tick
+    }
+
+    pub fn time_scale(&self) -> u64 {
+        self.time_scale
+    }
+
+    pub fn equal_picture_interval(&self) -> bool {
+        self.equal_picture_interval
+    }
+
+    pub fn num_ticks_per_picture_minus_1(&self) -> u64 {
+        self.num_ticks_per_picture_minus_1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimingInfo;
+    use crate::{
+        bits::bitstream::BitStream,
+        error::{CarpError, ErrorKind},
+    };
+
+    #[test]
+    fn presentation_time() {
+        // 1001 units per tick at 30000 units per second, no equal picture interval
+        let mut bs = BitStream::new(vec![0, 0, 0x03, 0xe9, 0, 0, 0x75, 0x30, 0]);
+        let timing_info = TimingInfo::new(&mut bs).unwrap();
+
+        assert!(!timing_info.equal_picture_interval);
+        assert_eq!(0.0, timing_info.presentation_time(0));
+        assert_eq!(1.001, timing_info.presentation_time(30));
+    }
+
+    #[test]
+    fn zero_time_scale() {
+        let mut bs = BitStream::new(vec![0, 0, 0, 1, 0, 0, 0, 0, 0]);
+
+        assert_eq!(
+            Some(CarpError::new(64, "time_scale", ErrorKind::InvalidValue(0))),
+            TimingInfo::new(&mut bs).err()
+        );
     }
 }
@@ -0,0 +1,291 @@
+use crate::{
+    bits::{
+        bitstream::BitStream,
+        stream_reader::{StreamFormat, OBU_TEMPORAL_DELIMITER},
+    },
+    error::{ErrorKind, Result},
+    obu::{
+        film_grain_params::FilmGrainParams,
+        frame_size::FrameSize,
+        global_motion_params::GlobalMotionParams,
+        loop_filter_params::LoopFilterParams,
+        obu_frame_header::{UncompressedHeader, NUM_REF_FRAMES, TOTAL_REFS_PER_FRAME},
+        obu_sequence_header::{ObuSequenceHeader, OperatingPointSelection},
+        open_bitstream_unit::OpenBitstreamUnit,
+        segmentation_params::SegmentationParams,
+        tile_info::TileInfo,
+    },
+    temporal_unit::TemporalUnit,
+};
+
+#[derive(Default)]
+pub struct Parser {
+    pub state: State,
+}
+
+#[derive(Default)]
+pub struct State {
+    pub sequence_header: Option<ObuSequenceHeader>,
+    pub operating_point_selection: OperatingPointSelection,
+    pub operating_point: usize,
+    pub operating_point_idc: u64,
+    pub order_hint: u64,
+    pub order_hint_bits: u64,
+    pub bit_depth: u64,
+    pub num_planes: u64,
+    pub seen_frame_header: bool,
+    pub tile_num: bool,
+    /// Tile info of the current frame, used by its tile groups
+    pub tile_info: TileInfo,
+    /// Header of the current frame and its bits, which frame header copies are compared to
+    pub frame_header: Option<UncompressedHeader>,
+    pub frame_header_bits: Vec<u8>,
+    pub frame_header_bit_len: u64,
+    pub frame_is_intra: bool,
+    pub ref_frame_type: [u64; NUM_REF_FRAMES],
+    pub ref_valid: [bool; NUM_REF_FRAMES],
+    pub ref_order_hint: [u64; NUM_REF_FRAMES],
+    pub order_hints: [u64; TOTAL_REFS_PER_FRAME],
+    pub current_frame_id: u64,
+    pub ref_frame_id: [u64; NUM_REF_FRAMES],
+    pub ref_frame_size: [FrameSize; NUM_REF_FRAMES],
+    pub ref_segmentation_params: [SegmentationParams; NUM_REF_FRAMES],
+    pub ref_loop_filter_params: [LoopFilterParams; NUM_REF_FRAMES],
+    pub ref_global_motion_params: [GlobalMotionParams; NUM_REF_FRAMES],
+    pub ref_film_grain_params: [FilmGrainParams; NUM_REF_FRAMES],
+    pub ref_frame_sign_bias: [u64; TOTAL_REFS_PER_FRAME],
+}
+
+impl State {
+    /// Signed distance between two order hints, 0 if order hints are disabled
+    pub fn get_relative_dist(&self, a: u64, b: u64) -> i64 {
+        if self.order_hint_bits == 0 {
+            return 0;
+        }
+
+        let diff = a as i64 - b as i64;
+        let m = 1 << (self.order_hint_bits - 1);
+
+        (diff & (m - 1)) - (diff & m)
+    }
+}
+
+impl Parser {
+    pub fn decode_temporal_unit(
+        &mut self,
+        data: Vec<u8>,
+        format: StreamFormat,
+        pts: Option<u64>,
+    ) -> Result<TemporalUnit> {
+        // Every temporal unit starts with a temporal delimiter, which ends the frame of the
+        // previous unit. Without one the container's unit boundary has to do.
+        let missing_temporal_delimiter =
+            first_obu_type(&data, format) != Some(OBU_TEMPORAL_DELIMITER);
+        if missing_temporal_delimiter {
+            self.state.seen_frame_header = false;
+        }
+
+        let temporal_unit_size = data.len() as u64;
+        let mut b = BitStream::new(data);
+
+        let obus = match format {
+            StreamFormat::AnnexB => self.temporal_unit(&mut b, temporal_unit_size)?,
+            StreamFormat::LowOverhead => self.low_overhead_temporal_unit(&mut b)?,
+        };
+
+        Ok(TemporalUnit {
+            pts,
+            obus,
+            missing_temporal_delimiter,
+        })
+    }
+
+    fn low_overhead_temporal_unit(&mut self, b: &mut BitStream) -> Result<Vec<OpenBitstreamUnit>> {
+        let mut obus = Vec::new();
+        while b.more_data_in_bitstream() {
+            let sz = b.bytes_remaining() as u64;
+            obus.extend(OpenBitstreamUnit::new(b, sz, &mut self.state)?);
+        }
+
+        Ok(obus)
+    }
+
+    fn temporal_unit(&mut self, b: &mut BitStream, size: u64) -> Result<Vec<OpenBitstreamUnit>> {
+        let mut obus = Vec::new();
+        let mut sz = size;
+        while sz > 0 {
+            let frame_unit_size = b.leb128("frame_unit_size")?;
+            sz = Parser::consume_size(b, "frame_unit_size", sz, b.leb_128_bytes)?;
+            sz = Parser::consume_size(b, "frame_unit_size", sz, frame_unit_size)?;
+            obus.extend(self.frame_unit(b, frame_unit_size)?);
+        }
+
+        Ok(obus)
+    }
+
+    fn frame_unit(&mut self, b: &mut BitStream, size: u64) -> Result<Vec<OpenBitstreamUnit>> {
+        let mut obus = Vec::new();
+        let mut sz = size;
+        while sz > 0 {
+            let obu_length = b.leb128("obu_length")?;
+            sz = Parser::consume_size(b, "obu_length", sz, b.leb_128_bytes)?;
+            sz = Parser::consume_size(b, "obu_length", sz, obu_length)?;
+
+            let start_position = b.get_position();
+            obus.extend(OpenBitstreamUnit::new(b, obu_length, &mut self.state)?);
+
+            let obu_bytes = (b.get_position() - start_position) as u64 / 8;
+            if obu_bytes != obu_length {
+                return Err(b.error(
+                    "obu_length",
+                    ErrorKind::SizeMismatch {
+                        expected: obu_length,
+                        actual: obu_bytes,
+                    },
+                ));
+            }
+        }
+
+        Ok(obus)
+    }
+
+    /// Takes `size` bytes from the `remaining` bytes of the enclosing unit
+    fn consume_size(
+        b: &BitStream,
+        element: &'static str,
+        remaining: u64,
+        size: u64,
+    ) -> Result<u64> {
+        remaining.checked_sub(size).ok_or_else(|| {
+            b.error(
+                element,
+                ErrorKind::SizeMismatch {
+                    expected: remaining,
+                    actual: size,
+                },
+            )
+        })
+    }
+}
+
+/// Type of the first OBU of a temporal unit, without parsing the OBU
+fn first_obu_type(data: &[u8], format: StreamFormat) -> Option<u8> {
+    let header_offset = match format {
+        // Skips frame_unit_size and obu_length
+        StreamFormat::AnnexB => {
+            let mut offset = 0;
+            for _ in 0..2 {
+                offset += data
+                    .get(offset..)?
+                    .iter()
+                    .position(|byte| byte & 0x80 == 0)?
+                    + 1;
+            }
+            offset
+        }
+        StreamFormat::LowOverhead => 0,
+    };
+
+    data.get(header_offset)
+        .map(|obu_header| (obu_header >> 3) & 0xf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parser, State};
+    use crate::{
+        bits::{bitstream::BitStream, stream_reader::StreamFormat},
+        error::{CarpError, ErrorKind},
+        obu::obu_header::ObuType,
+    };
+
+    #[test]
+    fn get_relative_dist() {
+        let state = State {
+            order_hint_bits: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(2, state.get_relative_dist(5, 3));
+        assert_eq!(-2, state.get_relative_dist(3, 5));
+        // Order hints wrap around after 8
+        assert_eq!(3, state.get_relative_dist(1, 6));
+        assert_eq!(0, State::default().get_relative_dist(1, 6));
+    }
+
+    #[test]
+    fn temporal_unit_layers() {
+        let mut p = Parser::default();
+
+        // Temporal delimiter, padding in temporal layer 1 and padding without extension
+        let data = vec![0x12, 0, 0x7e, 0x20, 1, 0xaa, 0x7a, 1, 0xbb];
+        let tu = p
+            .decode_temporal_unit(data, StreamFormat::LowOverhead, None)
+            .unwrap();
+
+        assert!(!tu.missing_temporal_delimiter);
+        assert_eq!(
+            vec![
+                ObuType::ObuTemporalDelimiter,
+                ObuType::ObuPadding,
+                ObuType::ObuPadding
+            ],
+            tu.obu_types()
+        );
+        assert_eq!(vec![(0, 0), (1, 0)], tu.layers());
+        assert_eq!(1, tu.layer_obus(1, 0).count());
+    }
+
+    #[test]
+    fn missing_temporal_delimiter() {
+        let mut p = Parser::default();
+        p.state.seen_frame_header = true;
+
+        let tu = p
+            .decode_temporal_unit(vec![0x7a, 1, 0xbb], StreamFormat::LowOverhead, None)
+            .unwrap();
+        assert!(tu.missing_temporal_delimiter);
+        assert!(!p.state.seen_frame_header);
+
+        let tu = p
+            .decode_temporal_unit(vec![2, 1, 0x10], StreamFormat::AnnexB, None)
+            .unwrap();
+        assert!(!tu.missing_temporal_delimiter);
+    }
+
+    #[test]
+    fn frame_unit_larger_than_temporal_unit() {
+        let mut b = BitStream::new(vec![3, 1, 0x10]);
+        let mut p = Parser::default();
+
+        assert_eq!(
+            Some(CarpError::new(
+                8,
+                "frame_unit_size",
+                ErrorKind::SizeMismatch {
+                    expected: 2,
+                    actual: 3
+                }
+            )),
+            p.temporal_unit(&mut b, 3).err()
+        );
+    }
+
+    #[test]
+    fn obu_larger_than_frame_unit() {
+        let mut b = BitStream::new(vec![2, 2, 0x10, 0]);
+        let mut p = Parser::default();
+
+        assert_eq!(
+            Some(CarpError::new(
+                16,
+                "obu_length",
+                ErrorKind::SizeMismatch {
+                    expected: 1,
+                    actual: 2
+                }
+            )),
+            p.temporal_unit(&mut b, 4).err()
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::obu::{obu_header::ObuType, open_bitstream_unit::OpenBitstreamUnit};
+
+/// All OBUs that share the same presentation time
+pub struct TemporalUnit {
+    /// Presentation timestamp from the container, if there is one
+    pub pts: Option<u64>,
+    /// OBUs of the unit in bitstream order, without the OBUs that were dropped because they
+    /// are not part of the operating point
+    pub obus: Vec<OpenBitstreamUnit>,
+    /// The unit did not start with a temporal delimiter, so its start is only known from the
+    /// container
+    pub missing_temporal_delimiter: bool,
+}
+
+impl TemporalUnit {
+    pub fn obu_types(&self) -> Vec<ObuType> {
+        self.obus.iter().map(|obu| obu.header.obu_type).collect()
+    }
+
+    /// Distinct `(temporal_id, spatial_id)` pairs of the OBUs in the unit, sorted.
+    ///
+    /// OBUs without an extension header belong to layer `(0, 0)`.
+    pub fn layers(&self) -> Vec<(u64, u64)> {
+        let mut layers: Vec<(u64, u64)> = self.obus.iter().map(layer).collect();
+        layers.sort_unstable();
+        layers.dedup();
+
+        layers
+    }
+
+    /// OBUs of a single layer in bitstream order
+    pub fn layer_obus(
+        &self,
+        temporal_id: u64,
+        spatial_id: u64,
+    ) -> impl Iterator<Item = &OpenBitstreamUnit> + '_ {
+        self.obus
+            .iter()
+            .filter(move |obu| layer(obu) == (temporal_id, spatial_id))
+    }
+}
+
+fn layer(obu: &OpenBitstreamUnit) -> (u64, u64) {
+    obu.header
+        .obu_extension_header
+        .as_ref()
+        .map_or((0, 0), |extension_header| {
+            (extension_header.temporal_id, extension_header.spatial_id)
+        })
+}
@@ -0,0 +1,36 @@
+use crate::{
+    obu::{
+        color_config::ColorConfig, obu_frame_header::NUM_REF_FRAMES,
+        obu_sequence_header::ObuSequenceHeader, operating_point::OperatingPoint,
+    },
+    State,
+};
+
+/// 4:2:0 sequence header with a single operating point and the given maximum frame size
+pub fn sequence_header(width: u64, height: u64) -> ObuSequenceHeader {
+    let bits = |size: u64| 64 - (size - 1).leading_zeros() as u64;
+
+    ObuSequenceHeader {
+        frame_width_bits_minus_1: bits(width) - 1,
+        frame_height_bits_minus_1: bits(height) - 1,
+        max_frame_width_minus_1: width - 1,
+        max_frame_height_minus_1: height - 1,
+        operating_points: vec![OperatingPoint::default()],
+        color_config: ColorConfig {
+            subsampling_x: true,
+            subsampling_y: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// State with 7 bit order hints and the order hints of every reference slot
+pub fn order_hint_state(ref_order_hint: [u64; NUM_REF_FRAMES], order_hint: u64) -> State {
+    State {
+        order_hint_bits: 7,
+        order_hint,
+        ref_order_hint,
+        ..Default::default()
+    }
+}